@@ -1,14 +1,57 @@
 var wasm = require('./pkg')
 module.exports = function detective (source, options) {
-  return wasm.detective(source.toString(), toOptions(options))
+  try {
+    return wasm.detective(source.toString(), toOptions(options))
+  } catch (err) {
+    throw toError(err)
+  }
 }
 module.exports.find = function find (source, options) {
-  return wasm.find(source.toString(), toOptions(options))
+  var found
+  try {
+    found = wasm.find(source.toString(), toOptions(options))
+  } catch (err) {
+    throw toError(err)
+  }
+  found.diagnostics = found.diagnostics.map(toError)
+  return found
 }
 
 function toOptions (options) {
   var wopts = wasm.Options.new()
   if (!options) return wopts
   if (options.word) wopts = wopts.word(options.word)
+  if (options.tolerant) wopts = wopts.tolerant(true)
+  var goal = options.goal || (options.parse && options.parse.sourceType)
+  if (goal) wopts = wopts.goal(toGoal(goal))
+  if (options.language) wopts = wopts.language(toLanguage(options.language))
   return wopts
 }
+
+function toGoal (goal) {
+  switch (goal) {
+    case 'script': return wasm.Goal.Script
+    case 'module': return wasm.Goal.Module
+    case 'auto': return wasm.Goal.Auto
+    default: throw new TypeError('detective-wasm: unknown goal "' + goal + '"')
+  }
+}
+
+function toLanguage (language) {
+  switch (language) {
+    case 'javascript': return wasm.Language.JavaScript
+    case 'jsx': return wasm.Language.Jsx
+    case 'flow': return wasm.Language.Flow
+    case 'typescript': return wasm.Language.TypeScript
+    default: throw new TypeError('detective-wasm: unknown language "' + language + '"')
+  }
+}
+
+function toError (err) {
+  if (!err || err instanceof Error) return err
+  var error = new SyntaxError(err.message + ' (' + err.line + ':' + err.column + ')')
+  error.kind = err.kind
+  error.pos = err.offset
+  error.loc = { line: err.line, column: err.column }
+  return error
+}
@@ -0,0 +1,59 @@
+use crate::{Error, Found, Options};
+
+#[cfg(feature = "oxc")]
+mod oxc;
+#[cfg(feature = "ressa")]
+mod ressa;
+
+#[cfg(not(any(feature = "ressa", feature = "oxc")))]
+compile_error!(
+    "require-detective needs at least one parser backend: enable the `ressa` or `oxc` feature"
+);
+
+/// The parser used for JavaScript sources. Which backends are available depends on the enabled
+/// cargo features; `Ressa` is the default when it is available.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    #[cfg(feature = "ressa")]
+    Ressa,
+    #[cfg(feature = "oxc")]
+    Oxc,
+}
+
+impl Default for Backend {
+    fn default() -> Self {
+        Self::all()[0]
+    }
+}
+
+impl Backend {
+    /// All backends that are compiled in.
+    pub fn all() -> &'static [Backend] {
+        &[
+            #[cfg(feature = "ressa")]
+            Backend::Ressa,
+            #[cfg(feature = "oxc")]
+            Backend::Oxc,
+        ]
+    }
+
+    /// Parse a backend name: `ressa` or `oxc`. Returns `None` for backends that are not compiled in.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            #[cfg(feature = "ressa")]
+            "ressa" => Some(Backend::Ressa),
+            #[cfg(feature = "oxc")]
+            "oxc" => Some(Backend::Oxc),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) fn find(source: &str, options: &Options) -> Result<Found, Error> {
+    match options.backend {
+        #[cfg(feature = "ressa")]
+        Backend::Ressa => ressa::find(source, options),
+        #[cfg(feature = "oxc")]
+        Backend::Oxc => oxc::find(source, options),
+    }
+}
@@ -0,0 +1,248 @@
+use crate::{lexer, lines, Error, ErrorKind, Found, Goal, Options, RequireKind};
+use oxc_allocator::Allocator;
+use oxc_ast::ast::*;
+use oxc_ast_visit::{walk, Visit};
+use oxc_diagnostics::OxcDiagnostic;
+use oxc_parser::{ParseOptions, Parser};
+use oxc_span::{GetSpan, SourceType};
+use oxc_syntax::scope::ScopeFlags;
+
+fn convert_error(error: &OxcDiagnostic, source: &str) -> Error {
+    let offset = error
+        .labels
+        .as_ref()
+        .and_then(|labels| labels.first())
+        .map_or(source.len(), |label| label.offset().min(source.len()));
+
+    // oxc does not categorize its errors, so guess from the message.
+    let message = error.message.to_string();
+    let lower = message.to_lowercase();
+    let kind = if lower.contains("module") || lower.contains("import") || lower.contains("export") {
+        ErrorKind::ModuleSyntax
+    } else if lower.contains("strict mode") {
+        ErrorKind::StrictMode
+    } else if lower.contains("already been declared") {
+        ErrorKind::Redeclaration
+    } else if lower.contains("regular expression") {
+        ErrorKind::InvalidRegex
+    } else if lower.contains("escape") {
+        ErrorKind::InvalidEscape
+    } else if lower.contains("unexpected end") || offset == source.len() {
+        ErrorKind::UnexpectedEof
+    } else if lower.contains("unexpected") || lower.contains("expected") {
+        ErrorKind::UnexpectedToken
+    } else {
+        ErrorKind::InvalidSyntax
+    };
+
+    Error::new(kind, message, source, offset)
+}
+
+struct Detective<'s, 'o> {
+    source: &'s str,
+    options: &'o Options,
+    found: Found,
+    functions: usize,
+    tries: usize,
+}
+
+impl<'s, 'o> Detective<'s, 'o> {
+    fn add(&mut self, specifier: String, kind: RequireKind, offset: u32) {
+        let (lazy, optional) = (self.functions > 0, self.tries > 0);
+        self.found
+            .add(specifier, kind, offset as usize, lazy, optional);
+    }
+
+    fn check(&mut self, callee: &Expression<'_>, args: &[Argument<'_>]) -> bool {
+        match callee {
+            Expression::Identifier(ident) if ident.name == self.options.word.as_str() => (),
+            _ => return false,
+        }
+        match args.first() {
+            Some(Argument::StringLiteral(string)) => {
+                self.add(
+                    string.value.to_string(),
+                    RequireKind::Require,
+                    string.span.start,
+                );
+            }
+            Some(Argument::TemplateLiteral(template)) if template.expressions.is_empty() => {
+                let cooked = template.quasis[0].value.cooked.as_ref();
+                self.add(
+                    cooked.map_or_else(String::new, |cooked| cooked.to_string()),
+                    RequireKind::Require,
+                    template.span.start,
+                );
+            }
+            Some(arg) => {
+                let span = arg.span();
+                self.found
+                    .expressions
+                    .push(self.source[span.start as usize..span.end as usize].to_string());
+            }
+            None => return false,
+        }
+        true
+    }
+
+    fn onsource(&mut self, source: &StringLiteral<'_>, kind: RequireKind) {
+        self.add(source.value.to_string(), kind, source.span.start);
+    }
+}
+
+impl<'a, 's, 'o> Visit<'a> for Detective<'s, 'o> {
+    fn visit_call_expression(&mut self, call: &CallExpression<'a>) {
+        if !self.check(&call.callee, &call.arguments) {
+            walk::walk_call_expression(self, call);
+        }
+    }
+
+    fn visit_new_expression(&mut self, new: &NewExpression<'a>) {
+        if !self.check(&new.callee, &new.arguments) {
+            walk::walk_new_expression(self, new);
+        }
+    }
+
+    fn visit_import_expression(&mut self, import: &ImportExpression<'a>) {
+        match &import.source {
+            Expression::StringLiteral(source) => self.onsource(source, RequireKind::DynamicImport),
+            _ => walk::walk_import_expression(self, import),
+        }
+    }
+
+    fn visit_function(&mut self, func: &Function<'a>, flags: ScopeFlags) {
+        self.functions += 1;
+        walk::walk_function(self, func, flags);
+        self.functions -= 1;
+    }
+
+    fn visit_arrow_function_expression(&mut self, arrow: &ArrowFunctionExpression<'a>) {
+        self.functions += 1;
+        walk::walk_arrow_function_expression(self, arrow);
+        self.functions -= 1;
+    }
+
+    fn visit_try_statement(&mut self, statement: &TryStatement<'a>) {
+        self.tries += 1;
+        self.visit_block_statement(&statement.block);
+        self.tries -= 1;
+        if let Some(handler) = &statement.handler {
+            self.visit_catch_clause(handler);
+        }
+        if let Some(finalizer) = &statement.finalizer {
+            self.visit_block_statement(finalizer);
+        }
+    }
+
+    fn visit_import_declaration(&mut self, import: &ImportDeclaration<'a>) {
+        self.onsource(&import.source, RequireKind::Import);
+    }
+
+    fn visit_export_all_declaration(&mut self, export: &ExportAllDeclaration<'a>) {
+        self.onsource(&export.source, RequireKind::Import);
+    }
+
+    fn visit_export_named_declaration(&mut self, export: &ExportNamedDeclaration<'a>) {
+        match &export.source {
+            Some(source) => self.onsource(source, RequireKind::Import),
+            None => walk::walk_export_named_declaration(self, export),
+        }
+    }
+}
+
+/// Parse `source` once, returning the errors and, unless the parser gave up, the requires found.
+fn parse(source: &str, options: &Options) -> (Vec<Error>, Option<Found>) {
+    let source_type = match options.goal {
+        Goal::Script => SourceType::cjs(),
+        Goal::Module => SourceType::mjs(),
+        Goal::Auto => SourceType::unambiguous(),
+    };
+    let allocator = Allocator::default();
+    let parsed = Parser::new(&allocator, source, source_type)
+        .with_options(ParseOptions {
+            allow_return_outside_function: true,
+            ..Default::default()
+        })
+        .parse();
+
+    let mut errors: Vec<Error> = parsed
+        .errors
+        .iter()
+        .map(|error| convert_error(error, source))
+        .collect();
+    if parsed.panicked {
+        return (errors, None);
+    }
+
+    // oxc accepts import and export declarations in scripts.
+    if options.goal == Goal::Script {
+        for statement in &parsed.program.body {
+            if let Some(declaration) = statement.as_module_declaration() {
+                errors.push(Error::new(
+                    ErrorKind::ModuleSyntax,
+                    "Cannot use import or export outside a module",
+                    source,
+                    declaration.span().start as usize,
+                ));
+            }
+        }
+    }
+
+    let goal = match options.goal {
+        Goal::Auto if parsed.module_record.has_module_syntax => Goal::Module,
+        Goal::Auto => Goal::Script,
+        goal => goal,
+    };
+    let mut detective = Detective {
+        source,
+        options,
+        found: Found {
+            goal,
+            ..Default::default()
+        },
+        functions: 0,
+        tries: 0,
+    };
+    detective.visit_program(&parsed.program);
+    (errors, Some(detective.found))
+}
+
+/// oxc recovers from many errors by itself. When it does have to give up, tolerant mode blanks
+/// out the line with the error and tries again, and falls back to the lexer if the line is
+/// already blank, like at the end of the source.
+pub(crate) fn find(source: &str, options: &Options) -> Result<Found, Error> {
+    let mut text = source.to_string();
+    let mut diagnostics: Vec<Error> = vec![];
+    loop {
+        let (errors, found) = parse(&text, options);
+        if !options.tolerant {
+            if let Some(error) = errors.first() {
+                return Err(error.clone());
+            }
+        }
+
+        let last_line = errors.last().map(|error| error.line);
+        for error in errors {
+            if !diagnostics.iter().any(|other| other.offset == error.offset) {
+                diagnostics.push(error);
+            }
+        }
+
+        if let Some(found) = found {
+            return Ok(Found {
+                diagnostics,
+                ..found
+            });
+        }
+
+        match last_line.and_then(|line| lines::blank_line(&text, line)) {
+            Some(blanked) => text = blanked,
+            None => {
+                return Ok(Found {
+                    diagnostics,
+                    ..lexer::find(source, options).locate(source)
+                })
+            }
+        }
+    }
+}
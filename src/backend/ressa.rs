@@ -0,0 +1,634 @@
+use crate::lexer::{self, Tokens};
+use crate::{lines, Error, ErrorKind, Found, Goal, Options, RequireKind};
+use resast::prelude::*;
+use ressa::Parser;
+
+fn convert_error(error: ressa::Error, source: &str) -> Error {
+    use ressa::Error as E;
+
+    let offset = match &error {
+        E::Scanner(error) => {
+            let mut offset = error.idx.min(source.len());
+            while !source.is_char_boundary(offset) {
+                offset -= 1;
+            }
+            offset
+        }
+        error => match error.position() {
+            Some(position) => lines::offset(source, position.line, position.column),
+            None => source.len(),
+        },
+    };
+
+    let (kind, message) = match error {
+        E::UnexpectedEoF => (
+            ErrorKind::UnexpectedEof,
+            "Unexpected end of input".to_string(),
+        ),
+        E::UnexpectedToken(_, message) => (ErrorKind::UnexpectedToken, message),
+        E::InvalidStartOfExpressionStmt(_, token) => (
+            ErrorKind::UnexpectedToken,
+            format!("Expression statement cannot start with {}", token),
+        ),
+        E::UseOfModuleFeatureOutsideOfModule(_, feature) => (
+            ErrorKind::ModuleSyntax,
+            format!("Cannot use {} outside a module", feature),
+        ),
+        E::InvalidImportError(_) => (ErrorKind::ModuleSyntax, "Invalid import".to_string()),
+        E::InvalidExportError(_) => (ErrorKind::ModuleSyntax, "Invalid export".to_string()),
+        E::DuplicateExport(_, name) => (
+            ErrorKind::ModuleSyntax,
+            format!("Duplicate export of {}", name),
+        ),
+        E::UndefinedExports(names) => (
+            ErrorKind::ModuleSyntax,
+            format!("Exported names are not defined: {}", names.join(", ")),
+        ),
+        E::HtmlCommentInModule(_) => (
+            ErrorKind::ModuleSyntax,
+            "HTML comments are not allowed in modules".to_string(),
+        ),
+        E::NonStrictFeatureInStrictContext(_, feature) => (
+            ErrorKind::StrictMode,
+            format!("{} is not allowed in strict mode", feature),
+        ),
+        E::StrictModeArgumentsOrEval(_) => (
+            ErrorKind::StrictMode,
+            "Cannot use arguments or eval as an identifier in strict mode".to_string(),
+        ),
+        E::OctalLiteral(_) => (
+            ErrorKind::StrictMode,
+            "Octal literals are not allowed in strict mode".to_string(),
+        ),
+        E::RestrictedIdent(_) => (
+            ErrorKind::StrictMode,
+            "Restricted word used as an identifier".to_string(),
+        ),
+        E::Redecl(_, name) => (
+            ErrorKind::Redeclaration,
+            format!("Label {} is already declared", name),
+        ),
+        E::LexicalRedecl(_, _, name) => (
+            ErrorKind::Redeclaration,
+            format!("Identifier {} has already been declared", name),
+        ),
+        E::InvalidRegEx(_, message) => (ErrorKind::InvalidRegex, message),
+        E::InvalidEscape(_, message) => (ErrorKind::InvalidEscape, message),
+        E::Scanner(error) => (ErrorKind::Tokenize, error.msg),
+        E::Other(error) => (ErrorKind::Other, error.to_string()),
+        error => (ErrorKind::InvalidSyntax, invalid_syntax_message(&error)),
+    };
+
+    Error::new(kind, message, source, offset)
+}
+
+/// ressa rejects `return` outside of functions, which Node allows in CommonJS modules because it
+/// wraps them in a function. If `error` is about such a `return`, replace the keyword by code of
+/// the same length, so that positions stay the same: `throw ` if the return has an argument on
+/// the same line, or `void 0` if it does not.
+fn allow_return(text: &str, error: &Error) -> Option<String> {
+    let rest = text.get(error.offset..)?.strip_prefix("return")?;
+    if error.kind != ErrorKind::UnexpectedToken
+        || !error.message.contains("return")
+        || rest.starts_with(|c: char| c.is_alphanumeric() || c == '_' || c == '$')
+    {
+        return None;
+    }
+    let rest = rest.trim_start_matches([' ', '\t']);
+    let argument =
+        !(rest.is_empty() || rest.starts_with([';', '}', '\r', '\n']) || rest.starts_with("//"));
+    let replacement = if argument { "throw " } else { "void 0" };
+    let mut text = text.to_string();
+    text.replace_range(error.offset..error.offset + replacement.len(), replacement);
+    Some(text)
+}
+
+fn invalid_syntax_message(error: &ressa::Error) -> String {
+    let message = error.to_string();
+    // ressa's messages embed the position as "at line:column"; strip it since we report it
+    // separately.
+    match error.position() {
+        Some(position) => message
+            .replace(&format!(" at {}", position), "")
+            .replace(&format!(": {}", position), ""),
+        None => message,
+    }
+}
+
+#[derive(Debug)]
+struct Detective<'a> {
+    options: &'a Options,
+    found: Found,
+    /// Address and length of the text being parsed. ressa borrows strings from the source, so
+    /// their offsets can be recovered from their addresses.
+    text: (usize, usize),
+    /// Offsets of the callees of calls with an argument that is not a string, whose text is
+    /// looked up in the tokens once parsing is done.
+    calls: Vec<usize>,
+    functions: usize,
+    tries: usize,
+}
+
+impl<'a> Detective<'a> {
+    fn new(options: &'a Options) -> Self {
+        Self {
+            options,
+            found: Default::default(),
+            text: (0, 0),
+            calls: vec![],
+            functions: 0,
+            tries: 0,
+        }
+    }
+
+    /// Offset of a string borrowed from the source, if it is.
+    fn position_of(&self, string: &str) -> Option<usize> {
+        let (start, len) = self.text;
+        let offset = (string.as_ptr() as usize).checked_sub(start)?;
+        (offset <= len).then_some(offset)
+    }
+
+    /// Offset of a string borrowed from the source, or 0 if it was not.
+    fn offset_of(&self, string: &str) -> usize {
+        self.position_of(string).unwrap_or(0)
+    }
+
+    /// Record a string literal as a dependency. The offset points at the opening quote.
+    fn add(&mut self, string: &StringLit<'_>, kind: RequireKind) {
+        // ressa keeps the raw text of string literals, escapes and all.
+        let raw = string.clone_inner();
+        let offset = self.offset_of(&raw).saturating_sub(1);
+        let (lazy, optional) = (self.functions > 0, self.tries > 0);
+        self.found
+            .add(lexer::unescape(&raw), kind, offset, lazy, optional);
+    }
+
+    fn check(&mut self, callee: &Expr<'_>, args: &[Expr<'_>]) -> bool {
+        if let Expr::Ident(ref ident) = callee {
+            if ident.name == "import" {
+                if let Some(Expr::Lit(Lit::String(string))) = args.first() {
+                    self.add(string, RequireKind::DynamicImport);
+                    return true;
+                }
+            }
+            if ident.name == self.options.word {
+                match args.first() {
+                    Some(Expr::Lit(Lit::String(string))) => {
+                        self.add(string, RequireKind::Require);
+                        return true;
+                    }
+                    Some(Expr::Lit(Lit::Template(template))) if template.expressions.is_empty() => {
+                        let quasi = &template.quasis[0];
+                        let offset = self.offset_of(&quasi.raw);
+                        let (lazy, optional) = (self.functions > 0, self.tries > 0);
+                        self.found.add(
+                            quasi.cooked.to_string(),
+                            RequireKind::Require,
+                            offset,
+                            lazy,
+                            optional,
+                        );
+                        return true;
+                    }
+                    Some(_) => {
+                        if let Some(offset) = self.position_of(&ident.name) {
+                            self.calls.push(offset);
+                        }
+                        return true;
+                    }
+                    _ => (),
+                }
+            }
+        }
+        false
+    }
+
+    fn oncall(&mut self, call: &CallExpr<'_>) {
+        if self.check(&call.callee, &call.arguments) {
+            return;
+        }
+
+        self.onexpr(&call.callee);
+        for arg in &call.arguments {
+            self.onexpr(arg);
+        }
+    }
+
+    fn ontemplate(&mut self, tpl: &TemplateLit<'_>) {
+        for expr in &tpl.expressions {
+            self.onexpr(expr);
+        }
+    }
+
+    fn onprop(&mut self, prop: &Prop) {
+        match &prop.key {
+            PropKey::Expr(expr) => self.onexpr(expr),
+            PropKey::Pat(pat) => self.onpat(pat),
+            _ => (),
+        }
+        match &prop.value {
+            PropValue::Expr(expr) => self.onexpr(expr),
+            PropValue::Pat(pat) => self.onpat(pat),
+            _ => (),
+        }
+    }
+
+    fn onparams(&mut self, params: &[FuncArg<'_>]) {
+        for param in params {
+            match param {
+                FuncArg::Expr(expr) => self.onexpr(expr),
+                FuncArg::Pat(pat) => self.onpat(pat),
+            }
+        }
+    }
+
+    fn onfunc(&mut self, func: &Func<'_>) {
+        self.functions += 1;
+        self.onparams(&func.params);
+        self.onbody(&func.body.0);
+        self.functions -= 1;
+    }
+
+    fn onclass(&mut self, class: &Class<'_>) {
+        if let Some(super_class) = &class.super_class {
+            self.onexpr(super_class);
+        }
+        for prop in class.body.0.iter() {
+            self.onprop(prop);
+        }
+    }
+
+    fn onexpr(&mut self, expr: &Expr<'_>) {
+        match expr {
+            Expr::Array(elements) => {
+                for el in elements.iter().flatten() {
+                    self.onexpr(el);
+                }
+            }
+            Expr::ArrowFunc(arrow) => {
+                self.functions += 1;
+                self.onparams(&arrow.params);
+                match &arrow.body {
+                    ArrowFuncBody::FuncBody(body) => self.onbody(&body.0),
+                    ArrowFuncBody::Expr(expr) => self.onexpr(expr),
+                }
+                self.functions -= 1;
+            }
+            Expr::Assign(assign) => {
+                match &assign.left {
+                    AssignLeft::Pat(pat) => self.onpat(pat),
+                    AssignLeft::Expr(expr) => self.onexpr(expr),
+                }
+                self.onexpr(&assign.right);
+            }
+            Expr::Await(expr) => self.onexpr(expr),
+            Expr::Binary(binary) => {
+                self.onexpr(&binary.left);
+                self.onexpr(&binary.right);
+            }
+            Expr::Class(class) => self.onclass(class),
+            Expr::Call(call) => self.oncall(call),
+            Expr::Conditional(cond) => {
+                self.onexpr(&cond.test);
+                self.onexpr(&cond.consequent);
+                self.onexpr(&cond.alternate);
+            }
+            Expr::Func(func) => self.onfunc(func),
+            Expr::Logical(op) => {
+                self.onexpr(&op.left);
+                self.onexpr(&op.right);
+            }
+            Expr::Member(member) => {
+                self.onexpr(&member.object);
+                self.onexpr(&member.property);
+            }
+            Expr::New(new) => {
+                if self.check(&new.callee, &new.arguments) {
+                    return;
+                }
+                self.onexpr(&new.callee);
+                for arg in &new.arguments {
+                    self.onexpr(arg);
+                }
+            }
+            Expr::Obj(obj) => {
+                for prop in obj.iter() {
+                    match prop {
+                        ObjProp::Prop(prop) => self.onprop(prop),
+                        ObjProp::Spread(expr) => self.onexpr(expr),
+                    }
+                }
+            }
+            Expr::Sequence(seq) => {
+                for expr in seq {
+                    self.onexpr(expr);
+                }
+            }
+            Expr::Spread(expr) => self.onexpr(expr),
+            Expr::TaggedTemplate(template) => {
+                self.onexpr(&template.tag);
+                self.ontemplate(&template.quasi);
+            }
+            Expr::Unary(expr) => self.onexpr(&expr.argument),
+            Expr::Update(expr) => self.onexpr(&expr.argument),
+            Expr::Yield(expr) if expr.argument.is_some() => {
+                self.onexpr(expr.argument.as_ref().unwrap())
+            }
+            _ => (),
+        }
+    }
+
+    fn onpat(&mut self, pat: &Pat<'_>) {
+        match pat {
+            Pat::Obj(obj) => {
+                for part in obj.iter() {
+                    match part {
+                        ObjPatPart::Assign(prop) => self.onprop(prop),
+                        ObjPatPart::Rest(pat) => self.onpat(pat),
+                    }
+                }
+            }
+            Pat::Array(array) => {
+                for part in array.iter() {
+                    match part {
+                        Some(ArrayPatPart::Pat(pat)) => self.onpat(pat),
+                        Some(ArrayPatPart::Expr(expr)) => self.onexpr(expr),
+                        _ => (),
+                    }
+                }
+            }
+            Pat::RestElement(rest) => self.onpat(rest),
+            Pat::Assign(assign) => {
+                self.onpat(&assign.left);
+                self.onexpr(&assign.right);
+            }
+            _ => (),
+        }
+    }
+
+    fn onvar(&mut self, decls: &[VarDecl]) {
+        for decl in decls.iter() {
+            self.onpat(&decl.id);
+            if let Some(init) = &decl.init {
+                self.onexpr(init);
+            }
+        }
+    }
+
+    fn ondecl(&mut self, decl: &Decl) {
+        match decl {
+            Decl::Var(_, decls) => self.onvar(decls),
+            Decl::Func(func) => self.onfunc(func),
+            Decl::Class(class) => self.onclass(class),
+            Decl::Import(import) => self.onsource(&import.source),
+            Decl::Export(export) => match &**export {
+                ModExport::Default(DefaultExportDecl::Decl(decl)) => self.ondecl(decl),
+                ModExport::Default(DefaultExportDecl::Expr(expr)) => self.onexpr(expr),
+                ModExport::Named(NamedExportDecl::Decl(decl)) => self.ondecl(decl),
+                ModExport::Named(NamedExportDecl::Specifier(_, Some(source))) => {
+                    self.onsource(source)
+                }
+                ModExport::All(source) => self.onsource(source),
+                _ => (),
+            },
+        };
+    }
+
+    fn onsource(&mut self, source: &Lit<'_>) {
+        if let Lit::String(string) = source {
+            self.add(string, RequireKind::Import);
+        }
+    }
+
+    fn onloopleft(&mut self, left: &LoopLeft<'_>) {
+        match &left {
+            LoopLeft::Expr(expr) => self.onexpr(expr),
+            LoopLeft::Variable(_, decl) => {
+                self.onpat(&decl.id);
+                if let Some(init) = &decl.init {
+                    self.onexpr(init);
+                }
+            }
+            LoopLeft::Pat(pat) => self.onpat(pat),
+        }
+    }
+
+    fn onstmt(&mut self, stmt: &Stmt<'_>) {
+        match stmt {
+            Stmt::Expr(expr) => self.onexpr(expr),
+            Stmt::Block(block) => self.onbody(&block.0),
+            Stmt::With(with) => {
+                self.onexpr(&with.object);
+                self.onstmt(&with.body);
+            }
+            Stmt::Return(Some(expr)) => self.onexpr(expr),
+            Stmt::Labeled(label) => self.onstmt(&label.body),
+            Stmt::If(stmt) => {
+                self.onexpr(&stmt.test);
+                self.onstmt(&stmt.consequent);
+                if let Some(alternate) = &stmt.alternate {
+                    self.onstmt(alternate);
+                }
+            }
+            Stmt::Switch(switch) => {
+                self.onexpr(&switch.discriminant);
+                for case in &switch.cases {
+                    if let Some(expr) = &case.test {
+                        self.onexpr(expr);
+                    }
+                    self.onbody(&case.consequent);
+                }
+            }
+            Stmt::Throw(err) => self.onexpr(err),
+            Stmt::Try(stmt) => {
+                self.tries += 1;
+                self.onbody(&stmt.block.0);
+                self.tries -= 1;
+                if let Some(catch) = &stmt.handler {
+                    if let Some(pat) = &catch.param {
+                        self.onpat(pat);
+                    }
+                    self.onbody(&catch.body.0);
+                }
+                if let Some(finalizer) = &stmt.finalizer {
+                    self.onbody(&finalizer.0);
+                }
+            }
+            Stmt::While(stmt) => {
+                self.onexpr(&stmt.test);
+                self.onstmt(&stmt.body);
+            }
+            Stmt::DoWhile(stmt) => {
+                self.onstmt(&stmt.body);
+                self.onexpr(&stmt.test);
+            }
+            Stmt::For(stmt) => {
+                match &stmt.init {
+                    Some(LoopInit::Variable(_, decls)) => self.onvar(decls),
+                    Some(LoopInit::Expr(expr)) => self.onexpr(expr),
+                    _ => (),
+                }
+                if let Some(test) = &stmt.test {
+                    self.onexpr(test);
+                }
+                if let Some(update) = &stmt.update {
+                    self.onexpr(update);
+                }
+                self.onstmt(&stmt.body);
+            }
+            Stmt::ForIn(stmt) => {
+                self.onloopleft(&stmt.left);
+                self.onexpr(&stmt.right);
+                self.onstmt(&stmt.body)
+            }
+            Stmt::ForOf(stmt) => {
+                self.onloopleft(&stmt.left);
+                self.onexpr(&stmt.right);
+                self.onstmt(&stmt.body)
+            }
+            Stmt::Var(var) => self.onvar(var),
+            _ => (),
+        }
+    }
+
+    fn onbody(&mut self, body: &[ProgramPart]) {
+        for part in body {
+            self.onpart(part);
+        }
+    }
+
+    fn onpart(&mut self, part: &ProgramPart<'_>) {
+        match part {
+            ProgramPart::Decl(decl) => self.ondecl(decl),
+            ProgramPart::Stmt(stmt) => self.onstmt(stmt),
+            _ => (),
+        }
+    }
+
+    /// Parse as much of `source` as possible. On failure, returns the error and the offset of the
+    /// statement that failed to parse.
+    fn parse_partial(&mut self, source: &str, goal: Goal) -> Option<(Error, usize)> {
+        self.text = (source.as_ptr() as usize, source.len());
+        let builder = Parser::builder()
+            .js(source)
+            .module(goal == Goal::Module)
+            .tolerant(self.options.tolerant);
+        let mut parser = match builder.build() {
+            Ok(parser) => parser,
+            Err(error) => return Some((convert_error(error, source), 0)),
+        };
+        loop {
+            let start = parser.next_position().start;
+            match parser.next() {
+                Some(Ok(part)) => self.onpart(&part),
+                Some(Err(error)) => {
+                    let start = lines::offset(source, start.line, start.column);
+                    return Some((convert_error(error, source), start));
+                }
+                None => return None,
+            }
+        }
+    }
+
+    /// Parse `source` statement by statement. When a statement fails to parse, the error is
+    /// recorded and parsing restarts on the line after the one where that statement began, so
+    /// requires that come after a syntax error are still found. Skipped lines are blanked out
+    /// rather than removed to keep reported positions intact.
+    fn find_tolerant(&mut self, source: &str, goal: Goal) {
+        let tokens = Tokens::new(source);
+        let mut text = source.to_string();
+        let mut remaining = text.clone();
+        // Where the remaining text starts. Errors that have no statement start there.
+        let mut first = 0;
+        while let Some((error, start)) = self.parse_partial(&remaining, goal) {
+            let start = start.max(first);
+            // Parse the statement with the top-level return again. The remaining text is the
+            // same as `text` from the start of the statement on.
+            if let Some(allowed) = allow_return(&text, &error) {
+                text = allowed;
+                remaining = lines::blank_before(&text, start);
+                continue;
+            }
+            // Start again after the statement with the error, or at the next line if the error
+            // is not followed by a `;` on its line.
+            let (start_line, _) = lines::position(&text, start);
+            let next_line = lines::line_start(&text, start_line + 1);
+            let semicolon = tokens.semicolon_after(error.offset.max(start));
+            if !self
+                .found
+                .diagnostics
+                .iter()
+                .any(|diagnostic| diagnostic.offset == error.offset)
+            {
+                self.found.diagnostics.push(error);
+            }
+            let offset = match next_line.into_iter().chain(semicolon).min() {
+                Some(offset) => offset,
+                None => break,
+            };
+            first = offset;
+            remaining = lines::blank_before(&text, offset);
+        }
+    }
+
+    fn parse(&mut self, source: &str, goal: Goal) -> Result<(), Error> {
+        let mut remaining = source.to_string();
+        while let Some((error, start)) = self.parse_partial(&remaining, goal) {
+            match allow_return(&remaining, &error) {
+                Some(allowed) => remaining = lines::blank_before(&allowed, start),
+                None => return Err(error),
+            }
+        }
+        Ok(())
+    }
+
+    fn find_with_goal(&mut self, source: &str, goal: Goal) -> Result<(), Error> {
+        self.found.goal = goal;
+        if self.options.tolerant {
+            self.find_tolerant(source, goal);
+            Ok(())
+        } else {
+            self.parse(source, goal)
+        }
+    }
+
+    fn find(mut self, source: &str) -> Result<Found, Error> {
+        if self.options.goal != Goal::Auto {
+            self.find_with_goal(source, self.options.goal)?;
+            return Ok(self.finish(source));
+        }
+
+        let result = self.find_with_goal(source, Goal::Script);
+        let is_module_syntax = |error: &Error| error.kind == ErrorKind::ModuleSyntax;
+        let retry = match &result {
+            Err(error) => is_module_syntax(error),
+            Ok(()) => self.found.diagnostics.iter().any(is_module_syntax),
+        };
+        if retry {
+            self.found = Default::default();
+            self.calls.clear();
+            self.find_with_goal(source, Goal::Module)?;
+        } else {
+            result?;
+        }
+        Ok(self.finish(source))
+    }
+
+    /// Fill in the text of the expressions the calls were made with.
+    fn finish(mut self, source: &str) -> Found {
+        if !self.calls.is_empty() {
+            let tokens = Tokens::new(source);
+            self.found.expressions = self
+                .calls
+                .iter()
+                .filter_map(|&offset| tokens.first_argument(offset))
+                .map(String::from)
+                .collect();
+        }
+        self.found
+    }
+}
+
+pub(crate) fn find(source: &str, options: &Options) -> Result<Found, Error> {
+    Detective::new(options).find(source)
+}
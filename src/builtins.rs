@@ -0,0 +1,263 @@
+use crate::{Found, SpecifierClass};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::fmt;
+
+/// A Node.js version number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NodeVersion(pub u32, pub u32, pub u32);
+
+impl NodeVersion {
+    /// Parse a version like `18`, `18.12` or `v18.12.1`. Missing parts are 0.
+    pub fn parse(version: &str) -> Option<Self> {
+        let mut parts = version.trim().trim_start_matches('v').split('.');
+        let mut part = || match parts.next() {
+            Some(part) => part.parse().ok(),
+            None => Some(0),
+        };
+        let version = NodeVersion(part()?, part()?, part()?);
+        parts.next().is_none().then_some(version)
+    }
+
+    /// The lowest version allowed by a package.json `engines.node` range, like `>=14.17` or
+    /// `^16.0.0 || >=18`. Only the first version in the range is considered.
+    pub fn from_engines(range: &str) -> Option<Self> {
+        let start = range.find(|c: char| c.is_ascii_digit())?;
+        let end = range[start..]
+            .find(|c: char| !c.is_ascii_digit() && c != '.')
+            .map_or(range.len(), |end| start + end);
+        Self::parse(range[start..end].trim_end_matches('.'))
+    }
+}
+
+impl fmt::Display for NodeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.0, self.1, self.2)
+    }
+}
+
+/// Versions serialize as strings, like `"14.18.0"`.
+#[cfg(feature = "serde")]
+impl serde::Serialize for NodeVersion {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// A Node core module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Builtin {
+    /// The name without the `node:` prefix.
+    pub name: &'static str,
+    /// The first release where the module can be required without flags. Modules that have been
+    /// around since before 0.10 are listed as 0.10.0.
+    pub since: NodeVersion,
+    /// Whether the module can only be required with the `node:` prefix.
+    pub prefix_only: bool,
+}
+
+/// The first release where `require('node:…')` works. `import` supported it a little earlier.
+pub const NODE_PREFIX_SINCE: NodeVersion = NodeVersion(14, 18, 0);
+
+const fn builtin(name: &'static str, major: u32, minor: u32, patch: u32) -> Builtin {
+    Builtin {
+        name,
+        since: NodeVersion(major, minor, patch),
+        prefix_only: false,
+    }
+}
+
+const fn prefix_only(name: &'static str, major: u32, minor: u32, patch: u32) -> Builtin {
+    Builtin {
+        prefix_only: true,
+        ..builtin(name, major, minor, patch)
+    }
+}
+
+const BUILTINS: &[Builtin] = &[
+    builtin("assert", 0, 10, 0),
+    builtin("assert/strict", 15, 0, 0),
+    builtin("async_hooks", 8, 1, 0),
+    builtin("buffer", 0, 10, 0),
+    builtin("child_process", 0, 10, 0),
+    builtin("cluster", 0, 10, 0),
+    builtin("console", 0, 10, 0),
+    builtin("constants", 0, 10, 0),
+    builtin("crypto", 0, 10, 0),
+    builtin("dgram", 0, 10, 0),
+    builtin("diagnostics_channel", 14, 17, 0),
+    builtin("dns", 0, 10, 0),
+    builtin("dns/promises", 15, 0, 0),
+    builtin("domain", 0, 10, 0),
+    builtin("events", 0, 10, 0),
+    builtin("fs", 0, 10, 0),
+    builtin("fs/promises", 14, 0, 0),
+    builtin("http", 0, 10, 0),
+    // Behind --expose-http2 since 8.4.0.
+    builtin("http2", 8, 8, 0),
+    builtin("https", 0, 10, 0),
+    builtin("inspector", 8, 0, 0),
+    builtin("inspector/promises", 19, 0, 0),
+    builtin("module", 0, 10, 0),
+    builtin("net", 0, 10, 0),
+    builtin("os", 0, 10, 0),
+    builtin("path", 0, 10, 0),
+    builtin("path/posix", 15, 3, 0),
+    builtin("path/win32", 15, 3, 0),
+    builtin("perf_hooks", 8, 5, 0),
+    builtin("process", 0, 10, 0),
+    builtin("punycode", 0, 10, 0),
+    builtin("querystring", 0, 10, 0),
+    builtin("readline", 0, 10, 0),
+    builtin("readline/promises", 17, 0, 0),
+    builtin("repl", 0, 10, 0),
+    builtin("stream", 0, 10, 0),
+    builtin("stream/consumers", 16, 7, 0),
+    builtin("stream/promises", 15, 0, 0),
+    builtin("stream/web", 16, 5, 0),
+    builtin("string_decoder", 0, 10, 0),
+    builtin("sys", 0, 10, 0),
+    builtin("timers", 0, 10, 0),
+    builtin("timers/promises", 15, 0, 0),
+    builtin("tls", 0, 10, 0),
+    builtin("trace_events", 10, 0, 0),
+    builtin("tty", 0, 10, 0),
+    builtin("url", 0, 10, 0),
+    builtin("util", 0, 10, 0),
+    builtin("util/types", 15, 3, 0),
+    builtin("v8", 1, 0, 0),
+    builtin("vm", 0, 10, 0),
+    // Behind --experimental-wasi-unstable-preview1 since 13.3.0.
+    builtin("wasi", 20, 0, 0),
+    // Behind --experimental-worker since 10.5.0.
+    builtin("worker_threads", 11, 7, 0),
+    builtin("zlib", 0, 10, 0),
+    prefix_only("sea", 20, 12, 0),
+    // Behind --experimental-sqlite since 22.5.0.
+    prefix_only("sqlite", 22, 13, 0),
+    prefix_only("test", 16, 17, 0),
+    prefix_only("test/reporters", 18, 17, 0),
+];
+
+/// Every Node core module.
+pub fn builtins() -> &'static [Builtin] {
+    BUILTINS
+}
+
+/// Look up the core module `specifier` refers to, like `fs` or `node:test`.
+pub fn builtin_module(specifier: &str) -> Option<&'static Builtin> {
+    let (name, prefixed) = match specifier.strip_prefix("node:") {
+        Some(name) => (name, true),
+        None => (specifier, false),
+    };
+    BUILTINS
+        .iter()
+        .find(|builtin| builtin.name == name && (prefixed || !builtin.prefix_only))
+}
+
+/// Check whether `specifier` refers to a Node core module, like `fs` or `node:test`.
+pub fn is_builtin(specifier: &str) -> bool {
+    builtin_module(specifier).is_some()
+}
+
+/// The Node version needed to require `specifier`, if it is a core module. The `node:` prefix
+/// needs `NODE_PREFIX_SINCE`.
+pub fn node_version_for(specifier: &str) -> Option<NodeVersion> {
+    let builtin = builtin_module(specifier)?;
+    match specifier.starts_with("node:") {
+        true => Some(builtin.since.max(NODE_PREFIX_SINCE)),
+        false => Some(builtin.since),
+    }
+}
+
+/// A require of a core module that is not available in the minimum supported Node version.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewerBuiltin {
+    pub specifier: String,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in bytes.
+    pub column: usize,
+    /// The Node version the require needs.
+    pub needs: NodeVersion,
+}
+
+/// Find the requires in `found` that need a newer Node than `minimum`.
+pub fn newer_builtins(found: &Found, minimum: NodeVersion) -> Vec<NewerBuiltin> {
+    found
+        .requires
+        .iter()
+        .filter(|require| matches!(require.class, SpecifierClass::Builtin { .. }))
+        .filter_map(|require| {
+            let needs = node_version_for(&require.specifier)?;
+            (needs > minimum).then(|| NewerBuiltin {
+                specifier: require.specifier.clone(),
+                line: require.line,
+                column: require.column,
+                needs,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find, Options};
+
+    #[test]
+    fn versions() {
+        assert_eq!(NodeVersion::parse("v18.12.1"), Some(NodeVersion(18, 12, 1)));
+        assert_eq!(NodeVersion::parse("16"), Some(NodeVersion(16, 0, 0)));
+        assert_eq!(NodeVersion::parse("16.x"), None);
+        assert_eq!(
+            NodeVersion::from_engines(">=14.17 <19"),
+            Some(NodeVersion(14, 17, 0))
+        );
+        assert_eq!(
+            NodeVersion::from_engines("^12.20.0 || >=14"),
+            Some(NodeVersion(12, 20, 0))
+        );
+        assert_eq!(NodeVersion::from_engines("*"), None);
+        assert_eq!(NodeVersion(14, 18, 0).to_string(), "14.18.0");
+    }
+
+    #[test]
+    fn catalog() {
+        assert!(is_builtin("fs"));
+        assert!(is_builtin("node:fs/promises"));
+        assert!(is_builtin("node:test"));
+        assert!(!is_builtin("test"));
+        assert!(!is_builtin("node:nope"));
+        assert_eq!(node_version_for("fs"), Some(NodeVersion(0, 10, 0)));
+        assert_eq!(node_version_for("node:fs"), Some(NODE_PREFIX_SINCE));
+        assert_eq!(
+            node_version_for("node:sqlite"),
+            Some(NodeVersion(22, 13, 0))
+        );
+        assert_eq!(node_version_for("lodash"), None);
+        assert!(builtins()
+            .iter()
+            .all(|builtin| builtin.since.0 > 0 || builtin.since.1 >= 10));
+    }
+
+    #[test]
+    fn newer() {
+        let source = "require('fs');\nrequire('worker_threads');\nrequire('node:path');\n\
+                      require('fs/promises');";
+        let found = find(source, &Options::new()).unwrap();
+        let newer = newer_builtins(&found, NodeVersion(12, 22, 0))
+            .into_iter()
+            .map(|newer| (newer.specifier, newer.line, newer.needs))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            newer,
+            vec![
+                ("node:path".to_string(), 3, NODE_PREFIX_SINCE),
+                ("fs/promises".to_string(), 4, NodeVersion(14, 0, 0)),
+            ]
+        );
+        assert!(newer_builtins(&found, NodeVersion(16, 0, 0)).is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+//! Compare the packages a project requires with the ones its package.json declares.
+
+use crate::{Found, Glob, SpecifierClass};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+/// The package.json fields that declare dependencies, in the order they are checked.
+const FIELDS: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+/// Where a package is required.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Usage {
+    pub file: PathBuf,
+    pub specifier: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+/// A declared dependency that is never required.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Unused {
+    pub name: String,
+    /// The package.json field it is declared in, like `devDependencies`.
+    pub field: String,
+}
+
+/// A required package that is not declared.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Missing {
+    pub name: String,
+    pub usages: Vec<Usage>,
+}
+
+/// Collects the packages required by the files of a package, to find unused and undeclared
+/// dependencies.
+#[derive(Debug, Clone)]
+pub struct DependencyCheck {
+    name: Option<String>,
+    declared: BTreeMap<String, String>,
+    used: BTreeMap<String, Vec<Usage>>,
+    ignore: Vec<Glob>,
+}
+
+impl DependencyCheck {
+    /// Start a check against the contents of a package.json.
+    pub fn new(package_json: &str) -> Result<Self, serde_json::Error> {
+        let json: serde_json::Value = serde_json::from_str(package_json)?;
+        let mut declared = BTreeMap::new();
+        for field in FIELDS {
+            if let Some(dependencies) = json.get(field).and_then(|value| value.as_object()) {
+                for name in dependencies.keys() {
+                    declared
+                        .entry(name.clone())
+                        .or_insert_with(|| field.to_string());
+                }
+            }
+        }
+        Ok(Self {
+            name: json
+                .get("name")
+                .and_then(|name| name.as_str())
+                .map(String::from),
+            declared,
+            used: BTreeMap::new(),
+            ignore: vec![],
+        })
+    }
+
+    /// Leave out packages whose name matches `glob` from both reports, like tools that are only
+    /// used from package.json scripts.
+    pub fn ignore(mut self, glob: Glob) -> Self {
+        self.ignore.push(glob);
+        self
+    }
+
+    /// Record the requires found in `file`.
+    pub fn add(&mut self, file: &Path, found: &Found) {
+        for require in &found.requires {
+            if let Some(name) = self.package_name(&require.class) {
+                self.used.entry(name.to_string()).or_default().push(Usage {
+                    file: file.to_path_buf(),
+                    specifier: require.specifier.clone(),
+                    line: require.line,
+                    column: require.column,
+                });
+            }
+        }
+    }
+
+    /// The package that `specifier` loads from `node_modules`, if any. Builtins only count if a
+    /// package by that name is declared, like the `events` or `buffer` shims for browsers.
+    fn package_name<'s>(&self, class: &'s SpecifierClass) -> Option<&'s str> {
+        let name = match class {
+            SpecifierClass::Package { name, .. } => name,
+            SpecifierClass::Builtin {
+                name,
+                prefixed: false,
+            } if self.declared.contains_key(name) => name,
+            _ => return None,
+        };
+        // A package may require itself by name.
+        (self.name.as_deref() != Some(name)).then_some(name)
+    }
+
+    fn is_ignored(&self, name: &str) -> bool {
+        self.ignore.iter().any(|glob| glob.is_match(name))
+    }
+
+    /// Declared dependencies that are never required. Type packages under `@types/` are
+    /// skipped, because they are used by the type checker.
+    pub fn unused(&self) -> Vec<Unused> {
+        self.declared
+            .iter()
+            .filter(|(name, _)| !self.used.contains_key(*name))
+            .filter(|(name, _)| !name.starts_with("@types/") && !self.is_ignored(name))
+            .map(|(name, field)| Unused {
+                name: name.clone(),
+                field: field.clone(),
+            })
+            .collect()
+    }
+
+    /// Required packages that are not declared in any dependency field.
+    pub fn missing(&self) -> Vec<Missing> {
+        self.used
+            .iter()
+            .filter(|(name, _)| !self.declared.contains_key(*name) && !self.is_ignored(name))
+            .map(|(name, usages)| Missing {
+                name: name.clone(),
+                usages: usages.clone(),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find, Options};
+
+    #[test]
+    fn check() {
+        let mut check = DependencyCheck::new(
+            r#"{
+                "name": "app",
+                "dependencies": { "lodash": "^4", "left-pad": "^1", "events": "^3" },
+                "devDependencies": { "tape": "^5", "eslint": "^9", "@types/node": "*" },
+                "peerDependencies": { "react": "*" },
+                "optionalDependencies": { "@scope/opt": "*" }
+            }"#,
+        )
+        .unwrap()
+        .ignore(Glob::new("eslint*"));
+
+        let source = "require('lodash/map'); require('fs'); require('node:path');\n\
+                      require('./local'); require('#internal'); require('app/lib');\n\
+                      import('@scope/opt/sub'); require('events'); require('react');\n\
+                      require('chalk'); require('@babel/core');";
+        let found = find(source, &Options::new()).unwrap();
+        check.add(Path::new("index.js"), &found);
+        let found = find("require('chalk')", &Options::new()).unwrap();
+        check.add(Path::new("test.js"), &found);
+
+        assert_eq!(
+            check.unused(),
+            vec![
+                Unused {
+                    name: "left-pad".to_string(),
+                    field: "dependencies".to_string()
+                },
+                Unused {
+                    name: "tape".to_string(),
+                    field: "devDependencies".to_string()
+                },
+            ]
+        );
+
+        let missing = check.missing();
+        let names = missing
+            .iter()
+            .map(|missing| missing.name.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["@babel/core", "chalk"]);
+        assert_eq!(
+            missing[1].usages[0],
+            Usage {
+                file: PathBuf::from("index.js"),
+                specifier: "chalk".to_string(),
+                line: 4,
+                column: 9,
+            }
+        );
+        assert_eq!(missing[1].usages[1].file, PathBuf::from("test.js"));
+    }
+}
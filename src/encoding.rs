@@ -0,0 +1,106 @@
+use crate::{Error, ErrorKind, Options};
+use std::borrow::Cow;
+
+const UTF8_BOM: &[u8] = b"\xEF\xBB\xBF";
+const UTF16LE_BOM: &[u8] = b"\xFF\xFE";
+const UTF16BE_BOM: &[u8] = b"\xFE\xFF";
+
+/// Decode a source file to text. A UTF-8 byte order mark is removed, and a UTF-16 byte order mark
+/// switches to decoding UTF-16. Anything else must be UTF-8: invalid bytes are an
+/// `ErrorKind::Encoding` error, or replaced by U+FFFD if the `lossy` option is set.
+///
+/// Offsets in errors and diagnostics for the decoded source are relative to the decoded text.
+pub fn decode<'s>(source: &'s [u8], options: &Options) -> Result<Cow<'s, str>, Error> {
+    if let Some(source) = source.strip_prefix(UTF16LE_BOM) {
+        return decode_utf16(source, u16::from_le_bytes, options).map(Cow::Owned);
+    }
+    if let Some(source) = source.strip_prefix(UTF16BE_BOM) {
+        return decode_utf16(source, u16::from_be_bytes, options).map(Cow::Owned);
+    }
+
+    let source = source.strip_prefix(UTF8_BOM).unwrap_or(source);
+    match std::str::from_utf8(source) {
+        Ok(text) => Ok(Cow::Borrowed(text)),
+        Err(_) if options.lossy => Ok(String::from_utf8_lossy(source)),
+        Err(error) => {
+            let valid = std::str::from_utf8(&source[..error.valid_up_to()]).unwrap();
+            Err(Error::new(
+                ErrorKind::Encoding,
+                format!("Invalid UTF-8 byte 0x{:02X}", source[error.valid_up_to()]),
+                valid,
+                valid.len(),
+            ))
+        }
+    }
+}
+
+fn decode_utf16(
+    source: &[u8],
+    from_bytes: fn([u8; 2]) -> u16,
+    options: &Options,
+) -> Result<String, Error> {
+    let units = source
+        .chunks(2)
+        .map(|pair| match *pair {
+            [a, b] => from_bytes([a, b]),
+            // A dangling byte at the end is not a code unit.
+            _ => 0xDC00,
+        })
+        .collect::<Vec<u16>>();
+
+    let mut text = String::with_capacity(units.len());
+    for c in char::decode_utf16(units) {
+        match c {
+            Ok(c) => text.push(c),
+            Err(_) if options.lossy => text.push(char::REPLACEMENT_CHARACTER),
+            Err(error) => {
+                return Err(Error::new(
+                    ErrorKind::Encoding,
+                    format!(
+                        "Invalid UTF-16 code unit 0x{:04X}",
+                        error.unpaired_surrogate()
+                    ),
+                    &text,
+                    text.len(),
+                ))
+            }
+        }
+    }
+    Ok(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::find_bytes;
+
+    #[test]
+    fn byte_order_marks() {
+        let options = Options::new();
+        assert_eq!(
+            decode(b"\xEF\xBB\xBFrequire('a')", &options).unwrap(),
+            "require('a')"
+        );
+        assert_eq!(decode(b"\xFF\xFEa\0b\0", &options).unwrap(), "ab");
+        assert_eq!(decode(b"\xFE\xFF\0a\0b", &options).unwrap(), "ab");
+
+        let found = find_bytes(b"\xEF\xBB\xBFvar a = require('a')", &options).unwrap();
+        assert_eq!(found.strings, vec!["a"]);
+    }
+
+    #[test]
+    fn invalid() {
+        let source = b"require('a');\n// caf\xE9\nrequire('b');";
+        let error = decode(source, &Options::new()).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Encoding);
+        assert_eq!((error.line, error.column), (2, 7));
+        assert!(find_bytes(source, &Options::new()).is_err());
+
+        let found = find_bytes(source, &Options::new().lossy(true)).unwrap();
+        assert_eq!(found.strings, vec!["a", "b"]);
+
+        let error = decode(b"\xFF\xFEa\0\x00\xD8", &Options::new()).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::Encoding);
+        assert_eq!(error.column, 2);
+    }
+}
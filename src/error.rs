@@ -0,0 +1,149 @@
+use crate::lines;
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::fmt;
+
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    UnexpectedToken,
+    UnexpectedEof,
+    InvalidSyntax,
+    /// Import or export syntax in a script, or an invalid module.
+    ModuleSyntax,
+    /// Syntax that is not allowed in strict mode code.
+    StrictMode,
+    Redeclaration,
+    InvalidRegex,
+    InvalidEscape,
+    /// The source could not be split into tokens.
+    Tokenize,
+    /// The source is not valid UTF-8 or UTF-16.
+    Encoding,
+    /// The file could not be read.
+    Read,
+    Other,
+}
+
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub kind: ErrorKind,
+    pub message: String,
+    /// Byte offset into the source.
+    pub offset: usize,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in bytes.
+    pub column: usize,
+}
+
+impl Error {
+    pub(crate) fn new(
+        kind: ErrorKind,
+        message: impl Into<String>,
+        source: &str,
+        offset: usize,
+    ) -> Self {
+        let (line, column) = lines::position(source, offset);
+        Self {
+            kind,
+            message: message.into(),
+            offset,
+            line,
+            column,
+        }
+    }
+
+    /// Render the lines around the error, pointing at the column where it occurred.
+    ///
+    /// ```text
+    ///   1 | var a = require('a')
+    /// > 2 | var = 10
+    ///     |     ^
+    ///   3 | var c = require('c')
+    /// ```
+    pub fn code_frame(&self, source: &str) -> String {
+        let first = self.line.saturating_sub(2).max(1);
+        let last = self.line + 2;
+        let width = last.to_string().len();
+
+        let mut frame = String::new();
+        for line in first..=last {
+            let text = match lines::line_text(source, line) {
+                Some(text) => text,
+                None => break,
+            };
+            let marker = if line == self.line { '>' } else { ' ' };
+            frame.push_str(&format!(
+                "{} {:>width$} | {}\n",
+                marker,
+                line,
+                text,
+                width = width
+            ));
+            if line == self.line {
+                let indent: String = text
+                    .bytes()
+                    .take(self.column.saturating_sub(1))
+                    .map(|byte| if byte == b'\t' { '\t' } else { ' ' })
+                    .collect();
+                frame.push_str(&format!("  {:>width$} | {}^\n", "", indent, width = width));
+            }
+        }
+        frame
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}:{})", self.message, self.line, self.column)
+    }
+}
+
+impl std::error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find, Goal, Options};
+
+    #[test]
+    fn position() {
+        let source = "var a = require('a');\nvar = 10;\n";
+        let error = find(source, &Options::new()).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::UnexpectedToken);
+        assert_eq!((error.line, error.column), (2, 5));
+        assert_eq!(error.offset, 26);
+        assert_eq!(&source[error.offset..error.offset + 1], "=");
+    }
+
+    #[test]
+    fn module_syntax() {
+        let error = find(
+            "require('a');\nexport default 1",
+            &Options::new().goal(Goal::Script),
+        )
+        .unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ModuleSyntax);
+        assert_eq!(error.line, 2);
+    }
+
+    #[test]
+    fn code_frame() {
+        let source =
+            "var a = require('a');\nvar b = require('b');\nvar = 10;\nvar c = require('c');\n";
+        let error = find(source, &Options::new()).unwrap_err();
+        assert_eq!(
+            error.code_frame(source),
+            concat!(
+                "  1 | var a = require('a');\n",
+                "  2 | var b = require('b');\n",
+                "> 3 | var = 10;\n",
+                "    |     ^\n",
+                "  4 | var c = require('c');\n",
+                "  5 | \n",
+            )
+        );
+    }
+}
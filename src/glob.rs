@@ -0,0 +1,224 @@
+/// A glob pattern for matching relative file paths.
+///
+/// Supports `*` and `?` within a path segment, `**` for any number of segments, character classes
+/// like `[a-z]` or `[!.]`, and alternatives like `{js,mjs}`. A pattern without a `/` matches the
+/// file name in any directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Glob {
+    alternatives: Vec<Vec<String>>,
+}
+
+impl Glob {
+    pub fn new(pattern: &str) -> Self {
+        let alternatives = expand_braces(pattern)
+            .into_iter()
+            .map(|pattern| {
+                let mut segments = split(&pattern).map(String::from).collect::<Vec<String>>();
+                if segments.len() == 1 && !pattern.contains('/') {
+                    segments.insert(0, "**".to_string());
+                }
+                segments
+            })
+            .collect();
+        Self { alternatives }
+    }
+
+    pub fn is_match(&self, path: &str) -> bool {
+        let path = split(path).collect::<Vec<&str>>();
+        self.alternatives
+            .iter()
+            .any(|segments| match_segments(segments, &path))
+    }
+
+    /// Check whether `text` contains glob syntax.
+    pub fn is_glob(text: &str) -> bool {
+        text.contains(['*', '?', '[', '{'])
+    }
+
+    /// Split a pattern into the directory part without glob syntax and the rest, so that only the
+    /// base directory needs to be walked.
+    ///
+    /// `src/**/*.js` becomes `("src", "**/*.js")`.
+    pub fn split_base(pattern: &str) -> (&str, &str) {
+        let mut base = 0;
+        for (index, _) in pattern.match_indices('/') {
+            if Self::is_glob(&pattern[..index]) {
+                break;
+            }
+            base = index + 1;
+        }
+        match base {
+            0 => (".", pattern),
+            1 => ("/", &pattern[1..]),
+            _ => (&pattern[..base - 1], &pattern[base..]),
+        }
+    }
+}
+
+fn split(path: &str) -> impl Iterator<Item = &str> {
+    path.split(['/', '\\'])
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+}
+
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let open = match pattern.find('{') {
+        Some(open) => open,
+        None => return vec![pattern.to_string()],
+    };
+
+    let mut depth = 0;
+    let mut parts = vec![];
+    let mut part_start = open + 1;
+    for (index, c) in pattern[open..].char_indices() {
+        let index = open + index;
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 1 => {
+                parts.push(&pattern[part_start..index]);
+                let prefix = &pattern[..open];
+                return parts
+                    .into_iter()
+                    .flat_map(|part| {
+                        expand_braces(&format!("{}{}{}", prefix, part, &pattern[index + 1..]))
+                    })
+                    .collect();
+            }
+            '}' => depth -= 1,
+            ',' if depth == 1 => {
+                parts.push(&pattern[part_start..index]);
+                part_start = index + 1;
+            }
+            _ => (),
+        }
+    }
+
+    // An unclosed brace is matched literally.
+    vec![pattern.to_string()]
+}
+
+fn match_segments(pattern: &[String], path: &[&str]) -> bool {
+    match pattern.split_first() {
+        None => path.is_empty(),
+        Some((first, rest)) if first == "**" => {
+            (0..=path.len()).any(|skip| match_segments(rest, &path[skip..]))
+        }
+        Some((first, rest)) => match path.split_first() {
+            Some((segment, path)) => {
+                let pattern = first.chars().collect::<Vec<char>>();
+                let text = segment.chars().collect::<Vec<char>>();
+                match_segment(&pattern, &text) && match_segments(rest, path)
+            }
+            None => false,
+        },
+    }
+}
+
+fn match_segment(pattern: &[char], text: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('*', rest)) => (0..=text.len()).any(|skip| match_segment(rest, &text[skip..])),
+        Some(('?', rest)) => !text.is_empty() && match_segment(rest, &text[1..]),
+        Some(('[', rest)) => match (class_end(rest), text.split_first()) {
+            (Some(end), Some((&c, text))) => {
+                match_class(&rest[..end], c) && match_segment(&rest[end + 1..], text)
+            }
+            (Some(_), None) => false,
+            (None, _) => text.first() == Some(&'[') && match_segment(rest, &text[1..]),
+        },
+        Some((&c, rest)) => text.first() == Some(&c) && match_segment(rest, &text[1..]),
+    }
+}
+
+/// Index of the `]` closing a character class. A `]` right at the start is part of the class.
+fn class_end(class: &[char]) -> Option<usize> {
+    let start = match class.first() {
+        Some('!') | Some('^') => 1,
+        _ => 0,
+    };
+    class
+        .iter()
+        .skip(start + 1)
+        .position(|&c| c == ']')
+        .map(|index| index + start + 1)
+}
+
+fn match_class(class: &[char], c: char) -> bool {
+    let (negate, class) = match class.split_first() {
+        Some(('!', rest)) | Some(('^', rest)) => (true, rest),
+        _ => (false, class),
+    };
+    let mut matched = false;
+    let mut index = 0;
+    while index < class.len() {
+        if index + 2 < class.len() && class[index + 1] == '-' {
+            matched |= class[index] <= c && c <= class[index + 2];
+            index += 3;
+        } else {
+            matched |= class[index] == c;
+            index += 1;
+        }
+    }
+    matched != negate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wildcards() {
+        let glob = Glob::new("src/*.js");
+        assert!(glob.is_match("src/index.js"));
+        assert!(glob.is_match("./src/index.js"));
+        assert!(!glob.is_match("src/lib/index.js"));
+        assert!(!glob.is_match("index.js"));
+
+        let glob = Glob::new("src/**/*.?s");
+        assert!(glob.is_match("src/index.js"));
+        assert!(glob.is_match("src/a/b/index.ts"));
+        assert!(!glob.is_match("src/a/b/index.mjs"));
+
+        let glob = Glob::new("test/**");
+        assert!(glob.is_match("test"));
+        assert!(glob.is_match("test/fixtures/a.js"));
+        assert!(!glob.is_match("src/test.js"));
+    }
+
+    #[test]
+    fn file_names() {
+        let glob = Glob::new("*.min.js");
+        assert!(glob.is_match("a.min.js"));
+        assert!(glob.is_match("dist/a.min.js"));
+        assert!(!glob.is_match("a.js"));
+
+        let glob = Glob::new("node_modules");
+        assert!(glob.is_match("node_modules"));
+        assert!(glob.is_match("a/node_modules"));
+        assert!(!glob.is_match("a/node_modules/b"));
+    }
+
+    #[test]
+    fn classes_and_braces() {
+        let glob = Glob::new("[a-c]*.{js,mjs}");
+        assert!(glob.is_match("a.js"));
+        assert!(glob.is_match("cat.mjs"));
+        assert!(!glob.is_match("dog.js"));
+        assert!(!glob.is_match("a.ts"));
+
+        let glob = Glob::new("[!.]*");
+        assert!(glob.is_match("a"));
+        assert!(!glob.is_match(".git"));
+
+        assert!(Glob::new("a{b").is_match("a{b"));
+        assert!(Glob::new("a[b").is_match("a[b"));
+    }
+
+    #[test]
+    fn base() {
+        assert_eq!(Glob::split_base("src/**/*.js"), ("src", "**/*.js"));
+        assert_eq!(Glob::split_base("src/lib/*.js"), ("src/lib", "*.js"));
+        assert_eq!(Glob::split_base("*.js"), (".", "*.js"));
+        assert_eq!(Glob::split_base("/a/*.js"), ("/a", "*.js"));
+        assert_eq!(Glob::split_base("/*.js"), ("/", "*.js"));
+    }
+}
@@ -0,0 +1,246 @@
+//! Circular dependencies, found as strongly connected components of the graph.
+
+use super::{Graph, Target};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::collections::HashMap;
+
+/// How likely a cycle is to cause trouble at load time.
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Hazard {
+    /// The modules require each other while they load, so one of them sees the other's
+    /// `module.exports` before it is fully initialized.
+    Eager,
+    /// The cycle only closes through requires inside functions or dynamic imports, which usually
+    /// run after every module in it has loaded.
+    Deferred,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    /// Indices into `Graph::modules`, in ascending order.
+    pub modules: Vec<usize>,
+    /// The edges between modules of the cycle, as the index of the module and the index into its
+    /// `Module::edges`.
+    pub edges: Vec<(usize, usize)>,
+    pub hazard: Hazard,
+}
+
+impl Graph {
+    /// Find the circular dependencies in the graph, most hazardous first: eager cycles, then the
+    /// ones with the most eager edges.
+    pub fn cycles(&self) -> Vec<Cycle> {
+        let successors = |index: usize, eager_only: bool| {
+            self.modules[index]
+                .edges
+                .iter()
+                .filter(move |edge| !eager_only || edge.kind.is_eager())
+                .filter_map(|edge| match edge.target {
+                    Target::Module(target) => Some(target),
+                    _ => None,
+                })
+        };
+
+        let all = (0..self.modules.len()).collect::<Vec<_>>();
+        let mut cycles = strongly_connected(&all, |index| successors(index, false).collect())
+            .into_iter()
+            .filter(|component| {
+                component.len() > 1 || successors(component[0], false).any(|t| t == component[0])
+            })
+            .map(|mut modules| {
+                modules.sort_unstable();
+                let edges = modules
+                    .iter()
+                    .flat_map(|&index| {
+                        let modules = &modules;
+                        self.modules[index]
+                            .edges
+                            .iter()
+                            .enumerate()
+                            .filter(move |(_, edge)| match edge.target {
+                                Target::Module(target) => modules.binary_search(&target).is_ok(),
+                                _ => false,
+                            })
+                            .map(move |(edge, _)| (index, edge))
+                    })
+                    .collect::<Vec<_>>();
+
+                let eager = strongly_connected(&modules, |index| {
+                    successors(index, true)
+                        .filter(|target| modules.binary_search(target).is_ok())
+                        .collect()
+                })
+                .iter()
+                .any(|component| {
+                    component.len() > 1 || successors(component[0], true).any(|t| t == component[0])
+                });
+                let hazard = match eager {
+                    true => Hazard::Eager,
+                    false => Hazard::Deferred,
+                };
+                Cycle {
+                    modules,
+                    edges,
+                    hazard,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let eager_edges = |cycle: &Cycle| {
+            cycle
+                .edges
+                .iter()
+                .filter(|&&(module, edge)| self.modules[module].edges[edge].kind.is_eager())
+                .count()
+        };
+        cycles.sort_by(|a, b| {
+            a.hazard
+                .cmp(&b.hazard)
+                .then_with(|| eager_edges(b).cmp(&eager_edges(a)))
+                .then_with(|| a.modules.cmp(&b.modules))
+        });
+        cycles
+    }
+}
+
+/// Tarjan's algorithm over `nodes`, without recursion so deep graphs do not overflow the stack.
+pub(super) fn strongly_connected(
+    nodes: &[usize],
+    successors: impl Fn(usize) -> Vec<usize>,
+) -> Vec<Vec<usize>> {
+    struct State {
+        index: usize,
+        lowlink: usize,
+        on_stack: bool,
+    }
+
+    let mut states: HashMap<usize, State> = HashMap::new();
+    let mut stack = vec![];
+    let mut components = vec![];
+
+    for &root in nodes {
+        if states.contains_key(&root) {
+            continue;
+        }
+        // Each frame is a node and the successors it has left to visit.
+        let mut frames = vec![(root, successors(root), 0)];
+        let index = states.len();
+        states.insert(
+            root,
+            State {
+                index,
+                lowlink: index,
+                on_stack: true,
+            },
+        );
+        stack.push(root);
+
+        while let Some((node, next, position)) = frames.last_mut() {
+            let node = *node;
+            if let Some(&successor) = next.get(*position) {
+                *position += 1;
+                match states.get(&successor) {
+                    None => {
+                        let index = states.len();
+                        states.insert(
+                            successor,
+                            State {
+                                index,
+                                lowlink: index,
+                                on_stack: true,
+                            },
+                        );
+                        stack.push(successor);
+                        frames.push((successor, successors(successor), 0));
+                    }
+                    Some(state) if state.on_stack => {
+                        let index = state.index;
+                        let state = states.get_mut(&node).unwrap();
+                        state.lowlink = state.lowlink.min(index);
+                    }
+                    Some(_) => (),
+                }
+                continue;
+            }
+
+            frames.pop();
+            let lowlink = states[&node].lowlink;
+            if let Some((parent, _, _)) = frames.last() {
+                let parent = states.get_mut(parent).unwrap();
+                parent.lowlink = parent.lowlink.min(lowlink);
+            }
+            if lowlink == states[&node].index {
+                let mut component = vec![];
+                while let Some(member) = stack.pop() {
+                    states.get_mut(&member).unwrap().on_stack = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GraphBuilder, MemoryFileSystem, Resolver};
+
+    #[test]
+    fn cycles() {
+        let fs = MemoryFileSystem::new()
+            .file(
+                "/app/index.js",
+                "require('./a'); require('./c'); require('./self')",
+            )
+            .file("/app/a.js", "require('./b')")
+            .file("/app/b.js", "require('./a')")
+            .file("/app/c.js", "require('./d')")
+            .file("/app/d.js", "module.exports = () => require('./c')")
+            .file("/app/self.js", "try { require('./self') } catch (e) {}");
+        let graph = GraphBuilder::with_resolver(Resolver::with_fs(fs)).build(&["/app/index.js"]);
+        let index = |file| graph.find(file).unwrap();
+
+        let cycles = graph.cycles();
+        let summary = cycles
+            .iter()
+            .map(|cycle| (cycle.modules.clone(), cycle.edges.len(), cycle.hazard))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    vec![index("/app/a.js"), index("/app/b.js")],
+                    2,
+                    Hazard::Eager
+                ),
+                (vec![index("/app/self.js")], 1, Hazard::Eager),
+                (
+                    vec![index("/app/c.js"), index("/app/d.js")],
+                    2,
+                    Hazard::Deferred
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn deep() {
+        let mut fs = MemoryFileSystem::new();
+        for i in 0..10_000 {
+            fs = fs.file(
+                format!("/app/{}.js", i),
+                format!("require('./{}')", (i + 1) % 10_000),
+            );
+        }
+        let graph = GraphBuilder::with_resolver(Resolver::with_fs(fs)).build(&["/app/0.js"]);
+        let cycles = graph.cycles();
+        assert_eq!(cycles.len(), 1);
+        assert_eq!(cycles[0].modules.len(), 10_000);
+    }
+}
@@ -0,0 +1,326 @@
+//! Render a graph for visualization tools.
+
+use super::{EdgeKind, Graph, Target};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::path::{Component, Path, PathBuf};
+
+/// The formats a graph can be exported to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Graphviz DOT.
+    Dot,
+    Mermaid,
+    GraphMl,
+}
+
+impl ExportFormat {
+    /// Parse a format name: `dot`, `mermaid` or `graphml`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "dot" => Some(ExportFormat::Dot),
+            "mermaid" => Some(ExportFormat::Mermaid),
+            "graphml" => Some(ExportFormat::GraphMl),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct ExportOptions {
+    root: Option<PathBuf>,
+    collapse_packages: bool,
+    color_edges: bool,
+}
+
+impl ExportOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Label files relative to this directory.
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    /// Show each package in `node_modules` as a single node, labelled with its name.
+    pub fn collapse_packages(mut self, collapse_packages: bool) -> Self {
+        self.collapse_packages = collapse_packages;
+        self
+    }
+
+    /// Color edges by their kind.
+    pub fn color_edges(mut self, color_edges: bool) -> Self {
+        self.color_edges = color_edges;
+        self
+    }
+}
+
+impl EdgeKind {
+    fn color(self) -> &'static str {
+        match self {
+            EdgeKind::Static => "#000000",
+            EdgeKind::Dynamic => "#1f77b4",
+            EdgeKind::Lazy => "#2ca02c",
+            EdgeKind::Optional => "#ff7f0e",
+        }
+    }
+}
+
+/// The nodes and edges to draw, after collapsing packages.
+struct View {
+    labels: Vec<String>,
+    edges: Vec<(usize, usize, EdgeKind)>,
+}
+
+impl View {
+    fn new(graph: &Graph, options: &ExportOptions) -> Self {
+        let mut labels = vec![];
+        let mut keys = HashMap::new();
+        let nodes = graph
+            .modules
+            .iter()
+            .map(|module| {
+                let package = package_dir(&module.file).filter(|_| options.collapse_packages);
+                let (key, label) = match package {
+                    Some((dir, name)) => (dir, name),
+                    None => (module.file.clone(), label(&module.file, options)),
+                };
+                *keys.entry(key).or_insert_with(|| {
+                    labels.push(label);
+                    labels.len() - 1
+                })
+            })
+            .collect::<Vec<_>>();
+
+        let mut seen = HashSet::new();
+        let mut edges = vec![];
+        for (index, module) in graph.modules.iter().enumerate() {
+            for edge in &module.edges {
+                if let Target::Module(target) = edge.target {
+                    let edge = (nodes[index], nodes[target], edge.kind);
+                    if edge.0 != edge.1 && seen.insert(edge) {
+                        edges.push(edge);
+                    }
+                }
+            }
+        }
+        Self { labels, edges }
+    }
+}
+
+fn label(file: &Path, options: &ExportOptions) -> String {
+    let relative = options
+        .root
+        .as_ref()
+        .and_then(|root| file.strip_prefix(root).ok())
+        .unwrap_or(file);
+    relative.display().to_string()
+}
+
+/// The directory and name of the `node_modules` package that contains `file`, if any.
+pub(crate) fn package_dir(file: &Path) -> Option<(PathBuf, String)> {
+    let components = file.components().collect::<Vec<_>>();
+    let node_modules = components
+        .iter()
+        .rposition(|component| component.as_os_str() == "node_modules")?;
+    let name = |index: usize| match components.get(index) {
+        Some(Component::Normal(name)) => name.to_str(),
+        _ => None,
+    };
+    let first = name(node_modules + 1)?;
+    let (name, len) = match first.starts_with('@') {
+        true => (format!("{}/{}", first, name(node_modules + 2)?), 3),
+        false => (first.to_string(), 2),
+    };
+    // The file itself is not a package directory.
+    if node_modules + len >= components.len() {
+        return None;
+    }
+    let dir = components[..node_modules + len].iter().collect();
+    Some((dir, name))
+}
+
+impl Graph {
+    pub fn export(&self, format: ExportFormat, options: &ExportOptions) -> String {
+        match format {
+            ExportFormat::Dot => self.to_dot(options),
+            ExportFormat::Mermaid => self.to_mermaid(options),
+            ExportFormat::GraphMl => self.to_graphml(options),
+        }
+    }
+
+    pub fn to_dot(&self, options: &ExportOptions) -> String {
+        let view = View::new(self, options);
+        let quote = |text: &str| format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""));
+
+        let mut dot = String::from("digraph dependencies {\n");
+        for (index, label) in view.labels.iter().enumerate() {
+            writeln!(dot, "  n{} [label={}];", index, quote(label)).unwrap();
+        }
+        for (from, to, kind) in &view.edges {
+            write!(dot, "  n{} -> n{}", from, to).unwrap();
+            if options.color_edges {
+                write!(
+                    dot,
+                    " [color={}, label={}]",
+                    quote(kind.color()),
+                    kind.name()
+                )
+                .unwrap();
+            }
+            dot.push_str(";\n");
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_mermaid(&self, options: &ExportOptions) -> String {
+        let view = View::new(self, options);
+
+        let mut mermaid = String::from("graph LR\n");
+        for (index, label) in view.labels.iter().enumerate() {
+            writeln!(
+                mermaid,
+                "  n{}[\"{}\"]",
+                index,
+                label.replace('"', "#quot;")
+            )
+            .unwrap();
+        }
+        for (from, to, kind) in &view.edges {
+            match options.color_edges {
+                true => writeln!(mermaid, "  n{} -->|{}| n{}", from, kind.name(), to),
+                false => writeln!(mermaid, "  n{} --> n{}", from, to),
+            }
+            .unwrap();
+        }
+        if options.color_edges {
+            for (index, (_, _, kind)) in view.edges.iter().enumerate() {
+                writeln!(mermaid, "  linkStyle {} stroke:{}", index, kind.color()).unwrap();
+            }
+        }
+        mermaid
+    }
+
+    pub fn to_graphml(&self, options: &ExportOptions) -> String {
+        let view = View::new(self, options);
+        let escape = |text: &str| {
+            text.replace('&', "&amp;")
+                .replace('<', "&lt;")
+                .replace('>', "&gt;")
+                .replace('"', "&quot;")
+        };
+
+        let mut xml = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+            "  <key id=\"kind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+        ));
+        if options.color_edges {
+            xml.push_str(
+                "  <key id=\"color\" for=\"edge\" attr.name=\"color\" attr.type=\"string\"/>\n",
+            );
+        }
+        xml.push_str("  <graph id=\"dependencies\" edgedefault=\"directed\">\n");
+        for (index, label) in view.labels.iter().enumerate() {
+            writeln!(
+                xml,
+                "    <node id=\"n{}\"><data key=\"label\">{}</data></node>",
+                index,
+                escape(label)
+            )
+            .unwrap();
+        }
+        for (index, (from, to, kind)) in view.edges.iter().enumerate() {
+            write!(
+                xml,
+                "    <edge id=\"e{}\" source=\"n{}\" target=\"n{}\"><data key=\"kind\">{}</data>",
+                index,
+                from,
+                to,
+                kind.name()
+            )
+            .unwrap();
+            if options.color_edges {
+                write!(xml, "<data key=\"color\">{}</data>", kind.color()).unwrap();
+            }
+            xml.push_str("</edge>\n");
+        }
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GraphBuilder, MemoryFileSystem, Resolver};
+
+    fn graph() -> Graph {
+        let fs = MemoryFileSystem::new()
+            .file(
+                "/app/index.js",
+                "require('./a'); require('@scope/pkg'); import('left-pad');",
+            )
+            .file("/app/a.js", "function f() { require('@scope/pkg/sub') }")
+            .file("/app/node_modules/@scope/pkg/index.js", "require('./sub')")
+            .file("/app/node_modules/@scope/pkg/sub.js", "")
+            .file("/app/node_modules/left-pad/index.js", "");
+        GraphBuilder::with_resolver(Resolver::with_fs(fs)).build(&["/app/index.js"])
+    }
+
+    #[test]
+    fn dot() {
+        let dot = graph().to_dot(&ExportOptions::new().root("/app"));
+        assert!(dot.starts_with("digraph dependencies {\n  n0 [label=\"index.js\"];\n"));
+        assert!(dot.contains("  n2 [label=\"node_modules/@scope/pkg/index.js\"];\n"));
+        assert!(dot.contains("  n0 -> n1;\n"));
+        assert_eq!(dot.matches("->").count(), 5);
+
+        let options = ExportOptions::new()
+            .root("/app")
+            .collapse_packages(true)
+            .color_edges(true);
+        let dot = graph().to_dot(&options);
+        assert!(dot.contains("  n2 [label=\"@scope/pkg\"];\n"));
+        assert!(dot.contains("  n0 -> n3 [color=\"#1f77b4\", label=dynamic];\n"));
+        assert!(dot.contains("  n1 -> n2 [color=\"#2ca02c\", label=lazy];\n"));
+        assert_eq!(dot.matches("->").count(), 4);
+    }
+
+    #[test]
+    fn mermaid() {
+        let options = ExportOptions::new()
+            .collapse_packages(true)
+            .color_edges(true);
+        let mermaid = graph().to_mermaid(&options);
+        assert!(mermaid.starts_with("graph LR\n  n0[\"/app/index.js\"]\n"));
+        assert!(mermaid.contains("  n0 -->|static| n1\n"));
+        assert!(mermaid.contains("  linkStyle 2 stroke:#1f77b4\n"));
+    }
+
+    #[test]
+    fn graphml() {
+        let graphml = graph().to_graphml(&ExportOptions::new().collapse_packages(true));
+        assert!(graphml.contains("<node id=\"n2\"><data key=\"label\">@scope/pkg</data></node>"));
+        assert!(graphml.contains(
+            "<edge id=\"e0\" source=\"n0\" target=\"n1\"><data key=\"kind\">static</data></edge>"
+        ));
+        assert!(!graphml.contains("color"));
+    }
+
+    #[test]
+    fn packages() {
+        assert_eq!(
+            package_dir(Path::new("/a/node_modules/b/node_modules/@c/d/e.js")),
+            Some((
+                PathBuf::from("/a/node_modules/b/node_modules/@c/d"),
+                "@c/d".to_string()
+            ))
+        );
+        assert_eq!(package_dir(Path::new("/a/b.js")), None);
+    }
+}
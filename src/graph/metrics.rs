@@ -0,0 +1,244 @@
+//! Per-module size and coupling metrics, to find modules worth splitting or loading lazily.
+
+use super::cycles::strongly_connected;
+use super::{Graph, Target};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::collections::VecDeque;
+
+/// Metrics of one module. Dependencies of every kind count, including lazy and dynamic ones.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metrics {
+    /// The number of modules that require this one.
+    pub fan_in: usize,
+    /// The number of modules this one requires.
+    pub fan_out: usize,
+    /// The number of other modules this one loads, directly or indirectly.
+    pub transitive: usize,
+    /// The size in bytes of this module and every module it loads.
+    pub transitive_size: usize,
+    /// The fewest requires it takes to get here from an entry.
+    pub depth: Option<usize>,
+    /// `fan_out / (fan_in + fan_out)`, from 0 for modules that only others depend on to 1 for
+    /// modules that only depend on others. 0 for isolated modules.
+    pub instability: f64,
+}
+
+/// A fixed size set of component indices.
+struct Bits(Vec<u64>);
+
+impl Bits {
+    fn new(len: usize) -> Self {
+        Bits(vec![0; len.div_ceil(64)])
+    }
+
+    fn insert(&mut self, index: usize) {
+        self.0[index / 64] |= 1 << (index % 64);
+    }
+
+    fn union(&mut self, other: &Bits) {
+        for (word, other) in self.0.iter_mut().zip(&other.0) {
+            *word |= other;
+        }
+    }
+
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(index, &word)| {
+            let mut word = word;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                word &= word - 1;
+                Some(index * 64 + bit)
+            })
+        })
+    }
+}
+
+/// The modules in a strongly connected component, and their total size.
+#[derive(Default, Clone, Copy)]
+struct Totals {
+    count: usize,
+    size: usize,
+}
+
+impl Graph {
+    /// The metrics of each module, in the order of `Graph::modules`.
+    pub fn metrics(&self) -> Vec<Metrics> {
+        let len = self.modules.len();
+        let successors = |index: usize| {
+            let mut targets = self.modules[index]
+                .edges
+                .iter()
+                .filter_map(|edge| match edge.target {
+                    Target::Module(target) => Some(target),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+            targets.sort_unstable();
+            targets.dedup();
+            targets
+        };
+        let successors = (0..len).map(successors).collect::<Vec<_>>();
+
+        let mut fan_in = vec![0; len];
+        for targets in &successors {
+            for &target in targets {
+                fan_in[target] += 1;
+            }
+        }
+
+        // Modules in a cycle reach the same modules, so reachability is computed between the
+        // components of the graph instead.
+        let all = (0..len).collect::<Vec<_>>();
+        let components = strongly_connected(&all, |index| successors[index].clone());
+        let mut component_of = vec![0; len];
+        let mut totals = vec![Totals::default(); components.len()];
+        for (component, modules) in components.iter().enumerate() {
+            for &module in modules {
+                component_of[module] = component;
+                totals[component].count += 1;
+                totals[component].size += self.modules[module].size;
+            }
+        }
+        let dependencies = components
+            .iter()
+            .enumerate()
+            .map(|(component, modules)| {
+                let mut targets = modules
+                    .iter()
+                    .flat_map(|&module| &successors[module])
+                    .map(|&target| component_of[target])
+                    .filter(|&target| target != component)
+                    .collect::<Vec<_>>();
+                targets.sort_unstable();
+                targets.dedup();
+                targets
+            })
+            .collect::<Vec<_>>();
+        let mut dependents = vec![0; components.len()];
+        for targets in &dependencies {
+            for &target in targets {
+                dependents[target] += 1;
+            }
+        }
+
+        // Components come out of Tarjan's algorithm after every component they reach, so the
+        // components a component reaches are known by the time it is visited. A set is dropped
+        // once every component that depends on it has been visited.
+        let mut reached: Vec<Option<Bits>> = Vec::with_capacity(components.len());
+        let mut transitive = Vec::with_capacity(components.len());
+        for targets in &dependencies {
+            let mut bits = Bits::new(components.len());
+            for &target in targets {
+                bits.insert(target);
+                if let Some(other) = &reached[target] {
+                    bits.union(other);
+                }
+            }
+            for &target in targets {
+                dependents[target] -= 1;
+                if dependents[target] == 0 {
+                    reached[target] = None;
+                }
+            }
+            transitive.push(bits.iter().fold(Totals::default(), |sum, other| Totals {
+                count: sum.count + totals[other].count,
+                size: sum.size + totals[other].size,
+            }));
+            let component = reached.len();
+            reached.push((dependents[component] > 0).then_some(bits));
+        }
+
+        let depths = self.depths(&successors);
+        (0..len)
+            .map(|index| {
+                let component = component_of[index];
+                // The other modules of its own cycle count too.
+                let (own, reached) = (totals[component], transitive[component]);
+                let fan_out = successors[index].len();
+                let coupling = fan_in[index] + fan_out;
+                Metrics {
+                    fan_in: fan_in[index],
+                    fan_out,
+                    transitive: reached.count + own.count - 1,
+                    transitive_size: reached.size + own.size,
+                    depth: depths[index],
+                    instability: match coupling {
+                        0 => 0.0,
+                        _ => fan_out as f64 / coupling as f64,
+                    },
+                }
+            })
+            .collect()
+    }
+
+    /// The distance of each module from the nearest entry.
+    fn depths(&self, successors: &[Vec<usize>]) -> Vec<Option<usize>> {
+        let mut depths = vec![None; self.modules.len()];
+        let mut queue = VecDeque::new();
+        for entry in self.entries() {
+            depths[entry] = Some(0);
+            queue.push_back(entry);
+        }
+        while let Some(index) = queue.pop_front() {
+            let depth = depths[index].map(|depth| depth + 1);
+            for &target in &successors[index] {
+                if depths[target].is_none() {
+                    depths[target] = depth;
+                    queue.push_back(target);
+                }
+            }
+        }
+        depths
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GraphBuilder, MemoryFileSystem, Resolver};
+
+    #[test]
+    fn metrics() {
+        let fs = MemoryFileSystem::new()
+            .file(
+                "/app/index.js",
+                "require('./a'); require('./a'); require('fs');",
+            )
+            .file("/app/a.js", "require('./b'); () => import('./c.js');")
+            .file("/app/b.js", "require('./a');")
+            .file("/app/c.js", "0123456789");
+        let graph = GraphBuilder::with_resolver(Resolver::with_fs(fs)).build(&["/app/index.js"]);
+        let metrics = graph.metrics();
+        let index = graph.find("/app/index.js").unwrap();
+        let a = graph.find("/app/a.js").unwrap();
+        let b = graph.find("/app/b.js").unwrap();
+        let c = graph.find("/app/c.js").unwrap();
+        let size = |module: usize| graph.modules[module].size;
+
+        assert_eq!((metrics[index].fan_in, metrics[index].fan_out), (0, 1));
+        assert_eq!(metrics[index].transitive, 3);
+        assert_eq!(
+            metrics[index].transitive_size,
+            size(index) + size(a) + size(b) + size(c)
+        );
+        assert_eq!(metrics[index].depth, Some(0));
+        assert_eq!(metrics[index].instability, 1.0);
+
+        // a and b require each other, so each counts the other but not itself.
+        assert_eq!((metrics[a].fan_in, metrics[a].fan_out), (2, 2));
+        assert_eq!(metrics[a].transitive, 2);
+        assert_eq!(metrics[b].transitive, 2);
+        assert_eq!(metrics[b].transitive_size, size(a) + size(b) + size(c));
+        assert_eq!(metrics[b].depth, Some(2));
+        assert_eq!(metrics[a].instability, 0.5);
+
+        assert_eq!(metrics[c].transitive, 0);
+        assert_eq!(metrics[c].transitive_size, 10);
+        assert_eq!(metrics[c].depth, Some(2));
+        assert_eq!(metrics[c].instability, 0.0);
+    }
+}
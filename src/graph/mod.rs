@@ -0,0 +1,432 @@
+//! Walk the dependencies of entry files into a graph, like browserify's module-deps.
+
+use crate::resolve::normalize;
+use crate::{
+    decode, find, Error, ErrorKind, FileSystem, Goal, Language, Options, OsFileSystem, Require,
+    RequireKind, Resolution, ResolveError, Resolver,
+};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+mod cycles;
+mod export;
+mod metrics;
+mod rows;
+mod rules;
+mod unreachable;
+mod why;
+
+pub use cycles::{Cycle, Hazard};
+pub use export::{ExportFormat, ExportOptions};
+pub use metrics::Metrics;
+pub use rows::Row;
+pub use rules::{Policy, Rule, Rules, Violation};
+pub use unreachable::PublishedFiles;
+
+/// How a dependency is loaded.
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EdgeKind {
+    /// Loaded as soon as the module runs.
+    Static,
+    /// Loaded by a dynamic `import()`.
+    Dynamic,
+    /// Loaded inside a function, so only when the function is called.
+    Lazy,
+    /// Loaded inside a `try` block, so a failure to load it may be handled.
+    Optional,
+}
+
+impl EdgeKind {
+    fn of(require: &Require) -> Self {
+        if require.kind == RequireKind::DynamicImport {
+            EdgeKind::Dynamic
+        } else if require.lazy {
+            EdgeKind::Lazy
+        } else if require.optional {
+            EdgeKind::Optional
+        } else {
+            EdgeKind::Static
+        }
+    }
+
+    /// Parse a kind name: `static`, `dynamic`, `lazy` or `optional`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "static" => Some(EdgeKind::Static),
+            "dynamic" => Some(EdgeKind::Dynamic),
+            "lazy" => Some(EdgeKind::Lazy),
+            "optional" => Some(EdgeKind::Optional),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EdgeKind::Static => "static",
+            EdgeKind::Dynamic => "dynamic",
+            EdgeKind::Lazy => "lazy",
+            EdgeKind::Optional => "optional",
+        }
+    }
+
+    /// Whether the dependency is loaded while the requiring module loads.
+    pub fn is_eager(self) -> bool {
+        matches!(self, EdgeKind::Static | EdgeKind::Optional)
+    }
+}
+
+/// What a dependency resolved to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Target {
+    /// An index into `Graph::modules`.
+    Module(usize),
+    Builtin(String),
+    /// A module replaced by `false` in a package.json `browser` field.
+    Empty,
+    Unresolved(ResolveError),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edge {
+    pub specifier: String,
+    pub kind: EdgeKind,
+    /// 1-based line number of the specifier.
+    pub line: usize,
+    /// 1-based column of the specifier, counted in bytes.
+    pub column: usize,
+    pub target: Target,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Module {
+    pub file: PathBuf,
+    pub entry: bool,
+    /// Size of the file in bytes.
+    pub size: usize,
+    /// The decoded source, if the graph was built with `GraphBuilder::sources`.
+    pub source: Option<String>,
+    pub edges: Vec<Edge>,
+    /// Why the file could not be read or parsed. Its edges are empty if so.
+    pub error: Option<Error>,
+}
+
+/// The modules reachable from a set of entry files. Modules are in the order they were found,
+/// starting with the entries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Graph {
+    pub modules: Vec<Module>,
+}
+
+impl Graph {
+    /// The index of the module for `file`.
+    pub fn find(&self, file: impl AsRef<Path>) -> Option<usize> {
+        let file = normalize(file.as_ref());
+        self.modules.iter().position(|module| module.file == file)
+    }
+
+    /// The indices of the entry modules.
+    pub fn entries(&self) -> impl Iterator<Item = usize> + '_ {
+        self.modules
+            .iter()
+            .enumerate()
+            .filter(|(_, module)| module.entry)
+            .map(|(index, _)| index)
+    }
+}
+
+/// Builds a `Graph` by scanning files with `find` and resolving what they require.
+pub struct GraphBuilder<F = OsFileSystem> {
+    resolver: Resolver<F>,
+    options: Options,
+    sources: bool,
+}
+
+impl Default for GraphBuilder {
+    fn default() -> Self {
+        Self::with_resolver(Resolver::new())
+    }
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<F: FileSystem> GraphBuilder<F> {
+    pub fn with_resolver(resolver: Resolver<F>) -> Self {
+        Self {
+            resolver,
+            options: Options::new(),
+            sources: false,
+        }
+    }
+
+    /// The options to scan files with. The goal is still picked by file extension where the
+    /// extension implies one, and so is the language of `.jsx` and TypeScript files.
+    pub fn options(mut self, options: Options) -> Self {
+        self.options = options;
+        self
+    }
+
+    /// Keep the source of each module in `Module::source`.
+    pub fn sources(mut self, sources: bool) -> Self {
+        self.sources = sources;
+        self
+    }
+
+    /// Scan `entries` and everything they depend on.
+    pub fn build<P: AsRef<Path>>(&self, entries: &[P]) -> Graph {
+        let mut graph = Graph::default();
+        let mut indices = HashMap::new();
+        let mut queue = VecDeque::new();
+
+        for entry in entries {
+            let index = add_module(&mut graph, &mut indices, normalize(entry.as_ref()));
+            graph.modules[index].entry = true;
+            queue.push_back(index);
+        }
+
+        while let Some(index) = queue.pop_front() {
+            let file = graph.modules[index].file.clone();
+            let requires = match self.scan(&mut graph.modules[index]) {
+                Ok(requires) => requires,
+                Err(error) => {
+                    graph.modules[index].error = Some(error);
+                    continue;
+                }
+            };
+
+            let dir = file.parent().unwrap_or(Path::new(""));
+            for require in requires {
+                let resolved = match require.kind {
+                    RequireKind::Require => self.resolver.resolve(&require.specifier, dir),
+                    RequireKind::Import | RequireKind::DynamicImport => {
+                        self.resolver.resolve_import(&require.specifier, dir)
+                    }
+                };
+                let target = match resolved {
+                    Ok(Resolution::File(path)) => {
+                        let known = indices.len();
+                        let target = add_module(&mut graph, &mut indices, path);
+                        if target == known {
+                            queue.push_back(target);
+                        }
+                        Target::Module(target)
+                    }
+                    Ok(Resolution::Builtin(name)) => Target::Builtin(name),
+                    Ok(Resolution::Empty) => Target::Empty,
+                    Err(error) => Target::Unresolved(error),
+                };
+                graph.modules[index].edges.push(Edge {
+                    kind: EdgeKind::of(&require),
+                    specifier: require.specifier,
+                    line: require.line,
+                    column: require.column,
+                    target,
+                });
+            }
+        }
+        graph
+    }
+
+    /// Read a module and find its dependencies. Files that are not JavaScript, like `.json` and
+    /// `.node` files, have none.
+    fn scan(&self, module: &mut Module) -> Result<Vec<Require>, Error> {
+        let bytes = self
+            .resolver
+            .fs()
+            .read(&module.file)
+            .map_err(|error| Error::new(ErrorKind::Read, error.to_string(), "", 0))?;
+        module.size = bytes.len();
+
+        let extension = module
+            .file
+            .extension()
+            .and_then(|extension| extension.to_str());
+        // Plain JavaScript files may still be JSX or Flow, so they use the language of the options.
+        let language = match extension.map(Language::from_extension) {
+            None | Some(Some(Language::JavaScript)) => Some(self.options.language),
+            Some(language) => language,
+        };
+        // Other files are only decoded to keep their source, and native `.node` addons not at all.
+        if language.is_none() && (!self.sources || extension == Some("node")) {
+            return Ok(vec![]);
+        }
+        let source = decode(&bytes, &self.options)?;
+        if self.sources {
+            module.source = Some(source.to_string());
+        }
+
+        let language = match language {
+            Some(language) => language,
+            None => return Ok(vec![]),
+        };
+        let mut options = self.options.clone().language(language);
+        if let Some(goal) = extension.and_then(Goal::from_extension) {
+            options = options.goal(goal);
+        }
+        Ok(find(&source, &options)?.requires)
+    }
+}
+
+fn add_module(graph: &mut Graph, indices: &mut HashMap<PathBuf, usize>, file: PathBuf) -> usize {
+    *indices.entry(file.clone()).or_insert_with(|| {
+        graph.modules.push(Module {
+            file,
+            entry: false,
+            size: 0,
+            source: None,
+            edges: vec![],
+            error: None,
+        });
+        graph.modules.len() - 1
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MemoryFileSystem;
+
+    fn fixture() -> GraphBuilder<MemoryFileSystem> {
+        let fs = MemoryFileSystem::new()
+            .file(
+                "/app/index.js",
+                "const a = require('./a');\n\
+                 function later() { return require('./b') }\n\
+                 try { require('optional') } catch (e) {}\n\
+                 import('./c.mjs');\n\
+                 require('fs');\n\
+                 require('./missing');",
+            )
+            .file("/app/a.js", "require('./b'); require('./data.json');")
+            .file("/app/b.js", "module.exports = require('./a');")
+            .file("/app/c.mjs", "import a from './a.js';\nexport default a;")
+            .file("/app/data.json", "{ \"require\": \"('./nope')\" }")
+            .file("/app/broken.js", "require('./a'); var = 1;");
+        GraphBuilder::with_resolver(Resolver::with_fs(fs))
+    }
+
+    #[test]
+    fn build() {
+        let graph = fixture().build(&["/app/index.js"]);
+        let files = graph
+            .modules
+            .iter()
+            .map(|module| module.file.to_str().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            files,
+            vec![
+                "/app/index.js",
+                "/app/a.js",
+                "/app/b.js",
+                "/app/c.mjs",
+                "/app/data.json"
+            ]
+        );
+        assert_eq!(graph.entries().collect::<Vec<_>>(), vec![0]);
+
+        let index = &graph.modules[0];
+        let edges = index
+            .edges
+            .iter()
+            .map(|edge| (edge.specifier.as_str(), edge.kind))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            edges,
+            vec![
+                ("./a", EdgeKind::Static),
+                ("./b", EdgeKind::Lazy),
+                ("optional", EdgeKind::Optional),
+                ("./c.mjs", EdgeKind::Dynamic),
+                ("fs", EdgeKind::Static),
+                ("./missing", EdgeKind::Static),
+            ]
+        );
+        assert_eq!(index.edges[0].target, Target::Module(1));
+        assert_eq!((index.edges[1].line, index.edges[1].column), (2, 35));
+        assert!(matches!(
+            index.edges[2].target,
+            Target::Unresolved(ResolveError::NotFound { .. })
+        ));
+        assert_eq!(index.edges[4].target, Target::Builtin("fs".to_string()));
+
+        // Cycles and modules required twice are only scanned once.
+        let b = graph.find("/app/b.js").unwrap();
+        assert_eq!(graph.modules[b].edges[0].target, Target::Module(1));
+        let c = graph.find("/app/c.mjs").unwrap();
+        assert_eq!(graph.modules[c].edges[0].target, Target::Module(1));
+
+        let json = graph.find("/app/data.json").unwrap();
+        assert!(graph.modules[json].edges.is_empty());
+        assert_eq!(graph.modules[json].size, 27);
+    }
+
+    #[test]
+    fn errors() {
+        let graph = fixture().build(&["/app/broken.js", "/app/nope.js"]);
+        assert_eq!(graph.modules.len(), 2);
+        assert!(graph.modules.iter().all(|module| module.entry));
+        assert!(graph.modules[0].error.is_some());
+        assert_eq!(
+            graph.modules[1].error.as_ref().map(|error| error.kind),
+            Some(ErrorKind::Read)
+        );
+
+        let graph = fixture()
+            .options(Options::new().tolerant(true))
+            .build(&["/app/broken.js"]);
+        assert!(graph.modules[0].error.is_none());
+    }
+
+    #[test]
+    fn conditions() {
+        let fs = MemoryFileSystem::new()
+            .file(
+                "/app/index.mjs",
+                "import a from 'pkg';\nconst b = require('pkg');\nimport('pkg');",
+            )
+            .file(
+                "/app/node_modules/pkg/package.json",
+                r#"{ "exports": { "import": "./esm.mjs", "require": "./cjs.js" } }"#,
+            )
+            .file("/app/node_modules/pkg/esm.mjs", "")
+            .file("/app/node_modules/pkg/cjs.js", "");
+        let graph = GraphBuilder::with_resolver(Resolver::with_fs(fs)).build(&["/app/index.mjs"]);
+        let targets = graph.modules[0]
+            .edges
+            .iter()
+            .map(|edge| match edge.target {
+                Target::Module(target) => graph.modules[target].file.to_str().unwrap(),
+                _ => "",
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            targets,
+            vec![
+                "/app/node_modules/pkg/esm.mjs",
+                "/app/node_modules/pkg/cjs.js",
+                "/app/node_modules/pkg/esm.mjs"
+            ]
+        );
+    }
+
+    #[test]
+    fn language() {
+        let fs = MemoryFileSystem::new()
+            .file("/app/index.js", "const a = <a>{require('./a.ts')}</a>;")
+            .file("/app/a.ts", "const a: string = require('./b');")
+            .file("/app/b.js", "");
+        let builder = GraphBuilder::with_resolver(Resolver::with_fs(fs));
+        let graph = builder
+            .options(Options::new().language(Language::Jsx))
+            .build(&["/app/index.js"]);
+        assert!(graph.modules.iter().all(|module| module.error.is_none()));
+        assert_eq!(graph.modules.len(), 3);
+    }
+}
@@ -0,0 +1,83 @@
+//! Rows in the shape browserify's module-deps emits, so a graph can stand in for it.
+
+use super::{Graph, Target};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use serde_json::{Map, Value};
+
+/// A module as module-deps describes it: `{ id, file, source, deps, entry }`.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    /// The module's file path, which module-deps uses as the id by default.
+    pub id: String,
+    pub file: String,
+    pub source: String,
+    /// Maps each specifier to the id it resolved to, or to `false` if it resolved to a builtin,
+    /// an empty module, or nothing at all.
+    pub deps: Map<String, Value>,
+    pub entry: bool,
+}
+
+impl Graph {
+    /// Describe the graph as module-deps rows. Build the graph with `GraphBuilder::sources` to
+    /// include the source of each module.
+    pub fn rows(&self) -> Vec<Row> {
+        let id = |index: usize| self.modules[index].file.display().to_string();
+        self.modules
+            .iter()
+            .enumerate()
+            .map(|(index, module)| Row {
+                id: id(index),
+                file: module.file.display().to_string(),
+                source: module.source.clone().unwrap_or_default(),
+                deps: module
+                    .edges
+                    .iter()
+                    .map(|edge| {
+                        let target = match edge.target {
+                            Target::Module(target) => Value::String(id(target)),
+                            _ => Value::Bool(false),
+                        };
+                        (edge.specifier.clone(), target)
+                    })
+                    .collect(),
+                entry: module.entry,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GraphBuilder, MemoryFileSystem, Resolver};
+    use serde_json::json;
+
+    #[test]
+    fn rows() {
+        let fs = MemoryFileSystem::new()
+            .file(
+                "/app/index.js",
+                "require('./a'); require('fs'); require('nope');",
+            )
+            .file("/app/a.js", "module.exports = require('./data.json');")
+            .file("/app/data.json", "{}");
+        let graph = GraphBuilder::with_resolver(Resolver::with_fs(fs))
+            .sources(true)
+            .build(&["/app/index.js"]);
+        let rows = graph.rows();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].id, "/app/index.js");
+        assert_eq!(
+            rows[0].source,
+            "require('./a'); require('fs'); require('nope');"
+        );
+        assert_eq!(
+            serde_json::to_value(&rows[0].deps).unwrap(),
+            json!({ "./a": "/app/a.js", "fs": false, "nope": false })
+        );
+        assert!(rows[0].entry);
+        assert!(!rows[1].entry);
+        assert_eq!(rows[2].source, "{}");
+    }
+}
@@ -0,0 +1,247 @@
+//! Check the edges of a graph against dependency rules, like "src/ui must not require src/db".
+
+use super::{Edge, EdgeKind, Graph, Target};
+use crate::{Glob, SpecifierClass};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+use std::path::{Path, PathBuf};
+
+/// What a rule does with the dependencies it matches.
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Policy {
+    /// Make an exception to the deny rules.
+    Allow,
+    Deny,
+}
+
+/// A pattern of dependencies. Every condition that is set has to match.
+#[derive(Debug, Clone)]
+pub struct Rule {
+    pub name: String,
+    pub policy: Policy,
+    from: Option<Glob>,
+    to: Option<Glob>,
+    package: Option<Glob>,
+    kinds: Vec<EdgeKind>,
+    deep: Option<bool>,
+}
+
+impl Rule {
+    pub fn new(name: &str, policy: Policy) -> Self {
+        Self {
+            name: name.to_string(),
+            policy,
+            from: None,
+            to: None,
+            package: None,
+            kinds: vec![],
+            deep: None,
+        }
+    }
+
+    pub fn allow(name: &str) -> Self {
+        Self::new(name, Policy::Allow)
+    }
+
+    pub fn deny(name: &str) -> Self {
+        Self::new(name, Policy::Deny)
+    }
+
+    /// Match dependencies of files that match `glob`, relative to `Rules::root`.
+    pub fn from(mut self, glob: Glob) -> Self {
+        self.from = Some(glob);
+        self
+    }
+
+    /// Match dependencies that resolve to a file that matches `glob`, relative to `Rules::root`.
+    pub fn to(mut self, glob: Glob) -> Self {
+        self.to = Some(glob);
+        self
+    }
+
+    /// Match requires of a package or Node core module whose name matches `pattern`, like
+    /// `lodash` or `@babel/*`. The pattern is matched against the whole name, whether or not the
+    /// dependency resolves.
+    pub fn package(mut self, pattern: &str) -> Self {
+        self.package = Some(Glob::new(&format!("./{}", pattern)));
+        self
+    }
+
+    /// Match dependencies loaded this way. Can be given more than once.
+    pub fn kind(mut self, kind: EdgeKind) -> Self {
+        self.kinds.push(kind);
+        self
+    }
+
+    /// Match only requires of a path inside a package, like `lodash/fp/map`, or only requires of
+    /// its main entry.
+    pub fn deep(mut self, deep: bool) -> Self {
+        self.deep = Some(deep);
+        self
+    }
+
+    fn is_match(&self, from: &Path, edge: &Edge, to: Option<&Path>) -> bool {
+        let matches = |glob: &Option<Glob>, path: Option<&Path>| match (glob, path) {
+            (None, _) => true,
+            (Some(glob), Some(path)) => glob.is_match(&path.to_string_lossy()),
+            (Some(_), None) => false,
+        };
+        if !matches(&self.from, Some(from)) || !matches(&self.to, to) {
+            return false;
+        }
+        if !self.kinds.is_empty() && !self.kinds.contains(&edge.kind) {
+            return false;
+        }
+        if self.package.is_none() && self.deep.is_none() {
+            return true;
+        }
+        let (name, deep) = match SpecifierClass::parse(&edge.specifier) {
+            SpecifierClass::Package { name, subpath } => (name, subpath != "."),
+            SpecifierClass::Builtin { name, .. } => (name, false),
+            _ => return false,
+        };
+        matches(&self.package, Some(Path::new(&name))) && self.deep.is_none_or(|d| d == deep)
+    }
+}
+
+/// A set of rules. A dependency breaks the rules if it matches a deny rule and no allow rule.
+#[derive(Debug, Clone, Default)]
+pub struct Rules {
+    root: Option<PathBuf>,
+    rules: Vec<Rule>,
+}
+
+impl Rules {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Match file globs against paths relative to this directory.
+    pub fn root(mut self, root: impl Into<PathBuf>) -> Self {
+        self.root = Some(root.into());
+        self
+    }
+
+    pub fn rule(mut self, rule: Rule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    fn relative<'p>(&self, file: &'p Path) -> &'p Path {
+        match &self.root {
+            Some(root) => file.strip_prefix(root).unwrap_or(file),
+            None => file,
+        }
+    }
+}
+
+/// A dependency that breaks a deny rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    /// The name of the first deny rule it matches.
+    pub rule: String,
+    /// An index into `Graph::modules`.
+    pub module: usize,
+    /// An index into the module's `Module::edges`.
+    pub edge: usize,
+}
+
+impl Graph {
+    /// The dependencies that break `rules`, in module order.
+    pub fn check(&self, rules: &Rules) -> Vec<Violation> {
+        let mut violations = vec![];
+        for (index, module) in self.modules.iter().enumerate() {
+            let from = rules.relative(&module.file);
+            for (edge_index, edge) in module.edges.iter().enumerate() {
+                let to = match edge.target {
+                    Target::Module(target) => Some(rules.relative(&self.modules[target].file)),
+                    _ => None,
+                };
+                let mut matching = rules
+                    .rules
+                    .iter()
+                    .filter(|rule| rule.is_match(from, edge, to));
+                let denied = matching.clone().find(|rule| rule.policy == Policy::Deny);
+                if let Some(rule) = denied {
+                    if !matching.any(|rule| rule.policy == Policy::Allow) {
+                        violations.push(Violation {
+                            rule: rule.name.clone(),
+                            module: index,
+                            edge: edge_index,
+                        });
+                    }
+                }
+            }
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GraphBuilder, MemoryFileSystem, Resolver};
+
+    #[test]
+    fn check() {
+        let fs = MemoryFileSystem::new()
+            .file(
+                "/app/src/ui/view.js",
+                "require('../db/query');\nrequire('lodash');\nrequire('lodash-es');\n\
+                 require('pkg/lib/internal');\nrequire('fs');\n\
+                 function later() { require('../db/cache') }",
+            )
+            .file("/app/src/ui/admin.js", "require('../db/query');")
+            .file("/app/src/db/query.js", "require('lodash/fp');")
+            .file("/app/src/db/cache.js", "")
+            .file("/app/node_modules/lodash/index.js", "")
+            .file("/app/node_modules/lodash/fp.js", "")
+            .file("/app/node_modules/lodash-es/index.js", "")
+            .file("/app/node_modules/pkg/lib/internal.js", "");
+        let graph = GraphBuilder::with_resolver(Resolver::with_fs(fs))
+            .build(&["/app/src/ui/view.js", "/app/src/ui/admin.js"]);
+
+        let rules = Rules::new()
+            .root("/app")
+            .rule(
+                Rule::deny("ui-no-db")
+                    .from(Glob::new("src/ui/**"))
+                    .to(Glob::new("src/db/**"))
+                    .kind(EdgeKind::Static),
+            )
+            .rule(Rule::allow("admin").from(Glob::new("src/ui/admin.js")))
+            .rule(Rule::deny("no-lodash").package("lodash"))
+            .rule(Rule::deny("no-deep").deep(true))
+            .rule(Rule::deny("no-fs").package("fs"));
+        let violations = graph
+            .check(&rules)
+            .into_iter()
+            .map(|violation| {
+                let module = &graph.modules[violation.module];
+                (
+                    violation.rule,
+                    module.edges[violation.edge].specifier.clone(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let expected = [
+            ("ui-no-db", "../db/query"),
+            ("no-lodash", "lodash"),
+            ("no-deep", "pkg/lib/internal"),
+            ("no-fs", "fs"),
+            ("no-lodash", "lodash/fp"),
+        ];
+        assert_eq!(
+            violations,
+            expected
+                .iter()
+                .map(|(rule, specifier)| (rule.to_string(), specifier.to_string()))
+                .collect::<Vec<_>>()
+        );
+    }
+}
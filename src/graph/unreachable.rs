@@ -0,0 +1,96 @@
+//! Files that no entry reaches.
+
+use super::Graph;
+use crate::resolve::normalize;
+use crate::Glob;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+
+/// The files a package publishes, from the package.json `files` field. Entries are paths relative
+/// to the package root, and match the files below them if they are directories.
+#[derive(Debug, Clone)]
+pub struct PublishedFiles {
+    globs: Vec<Glob>,
+}
+
+impl PublishedFiles {
+    pub fn new<S: AsRef<str>>(entries: &[S]) -> Self {
+        let globs = entries
+            .iter()
+            .flat_map(|entry| {
+                let entry = entry
+                    .as_ref()
+                    .trim_start_matches("./")
+                    .trim_end_matches('/');
+                [
+                    Glob::new(&format!("./{}", entry)),
+                    Glob::new(&format!("./{}/**", entry)),
+                ]
+            })
+            .collect();
+        Self { globs }
+    }
+
+    /// Check whether the file at `path`, relative to the package root, is published.
+    pub fn is_match(&self, path: &Path) -> bool {
+        let path = path.to_string_lossy();
+        self.globs.iter().any(|glob| glob.is_match(&path))
+    }
+}
+
+impl Graph {
+    /// The files in `files` that are not in the graph, in the same order.
+    pub fn unreachable(&self, files: &[PathBuf]) -> Vec<PathBuf> {
+        let reached = self
+            .modules
+            .iter()
+            .map(|module| &module.file)
+            .collect::<HashSet<_>>();
+        files
+            .iter()
+            .filter(|file| !reached.contains(&normalize(file)))
+            .cloned()
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GraphBuilder, MemoryFileSystem, Resolver};
+
+    #[test]
+    fn unreachable() {
+        let fs = MemoryFileSystem::new()
+            .file("/app/index.js", "require('./lib/a')")
+            .file("/app/lib/a.js", "")
+            .file("/app/lib/dead.js", "");
+        let graph = GraphBuilder::with_resolver(Resolver::with_fs(fs)).build(&["/app/index.js"]);
+        let files = [
+            "/app/index.js",
+            "/app/lib/./a.js",
+            "/app/lib/dead.js",
+            "/app/test.js",
+        ]
+        .iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>();
+        assert_eq!(
+            graph.unreachable(&files),
+            vec![
+                PathBuf::from("/app/lib/dead.js"),
+                PathBuf::from("/app/test.js")
+            ]
+        );
+    }
+
+    #[test]
+    fn published() {
+        let published = PublishedFiles::new(&["lib/", "./index.js", "bin/*.js"]);
+        assert!(published.is_match(Path::new("index.js")));
+        assert!(published.is_match(Path::new("lib/a/b.js")));
+        assert!(published.is_match(Path::new("bin/cli.js")));
+        assert!(!published.is_match(Path::new("test/index.js")));
+        assert!(!published.is_match(Path::new("bin/sub/cli.js")));
+    }
+}
@@ -0,0 +1,98 @@
+//! Explain why a module is in the graph.
+
+use super::export::package_dir;
+use super::{Graph, Target};
+use std::collections::VecDeque;
+
+impl Graph {
+    /// The modules in the `node_modules` package called `name`.
+    pub fn find_package(&self, name: &str) -> Vec<usize> {
+        self.modules
+            .iter()
+            .enumerate()
+            .filter(|(_, module)| package_dir(&module.file).is_some_and(|(_, n)| n == name))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// The shortest chain of requires from each entry to any of `targets`, shortest first. Each
+    /// hop is the index of a module and the index into its `Module::edges`. An entry that is a
+    /// target itself has an empty chain. Chains of the same length keep the order of the entries.
+    pub fn why(&self, targets: &[usize]) -> Vec<Vec<(usize, usize)>> {
+        let mut chains = self
+            .entries()
+            .filter_map(|entry| self.shortest_chain(entry, targets))
+            .collect::<Vec<_>>();
+        chains.sort_by_key(Vec::len);
+        chains
+    }
+
+    fn shortest_chain(&self, entry: usize, targets: &[usize]) -> Option<Vec<(usize, usize)>> {
+        // The hop each module was first reached by.
+        let mut reached_by = vec![None; self.modules.len()];
+        let mut visited = vec![false; self.modules.len()];
+        let mut queue = VecDeque::from([entry]);
+        visited[entry] = true;
+
+        while let Some(index) = queue.pop_front() {
+            if targets.contains(&index) {
+                let mut chain = vec![];
+                let mut current = index;
+                while let Some((module, edge)) = reached_by[current] {
+                    chain.push((module, edge));
+                    current = module;
+                }
+                chain.reverse();
+                return Some(chain);
+            }
+            for (edge, target) in self.modules[index].edges.iter().enumerate() {
+                if let Target::Module(target) = target.target {
+                    if !visited[target] {
+                        visited[target] = true;
+                        reached_by[target] = Some((index, edge));
+                        queue.push_back(target);
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{GraphBuilder, MemoryFileSystem, Resolver};
+
+    #[test]
+    fn why() {
+        let fs = MemoryFileSystem::new()
+            .file("/app/index.js", "require('./a'); require('./b')")
+            .file("/app/cli.js", "require('./b')")
+            .file("/app/a.js", "require('./c')")
+            .file("/app/b.js", "require('heavy')")
+            .file("/app/c.js", "require('heavy/lib/x')")
+            .file("/app/node_modules/heavy/index.js", "")
+            .file("/app/node_modules/heavy/lib/x.js", "");
+        let graph = GraphBuilder::with_resolver(Resolver::with_fs(fs))
+            .build(&["/app/index.js", "/app/cli.js"]);
+        let index = |file| graph.find(file).unwrap();
+
+        let heavy = graph.find_package("heavy");
+        assert_eq!(heavy.len(), 2);
+        let chains = graph.why(&heavy);
+        assert_eq!(
+            chains,
+            vec![
+                vec![(index("/app/index.js"), 1), (index("/app/b.js"), 0)],
+                vec![(index("/app/cli.js"), 0), (index("/app/b.js"), 0)],
+            ]
+        );
+
+        let chains = graph.why(&[index("/app/node_modules/heavy/lib/x.js")]);
+        assert_eq!(chains.len(), 1);
+        assert_eq!(chains[0].len(), 3);
+
+        assert_eq!(graph.why(&[index("/app/cli.js")]), vec![vec![]]);
+        assert!(graph.find_package("light").is_empty());
+    }
+}
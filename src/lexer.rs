@@ -0,0 +1,761 @@
+//! A lexical fallback detector for sources the parser does not understand, like JSX, Flow and
+//! TypeScript. It only tokenizes the source, so type annotations and JSX mostly pass through
+//! unnoticed, and then looks for token sequences that load a module.
+
+use crate::{Found, Goal, Options, RequireKind};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Kind<'a> {
+    Ident(&'a str),
+    /// A string literal or a template literal without substitutions, unescaped.
+    Str(String),
+    /// A template literal with substitutions.
+    Template,
+    Regex,
+    Punct(char),
+}
+
+#[derive(Debug, Clone)]
+struct Token<'a> {
+    kind: Kind<'a>,
+    start: usize,
+    end: usize,
+}
+
+impl<'a> Token<'a> {
+    fn is_ident(&self, name: &str) -> bool {
+        self.kind == Kind::Ident(name)
+    }
+
+    fn is_punct(&self, c: char) -> bool {
+        self.kind == Kind::Punct(c)
+    }
+
+    fn string(&self) -> Option<&str> {
+        match &self.kind {
+            Kind::Str(string) => Some(string),
+            _ => None,
+        }
+    }
+}
+
+/// Keywords after which a `/` starts a regular expression rather than a division.
+const REGEX_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_' || c == '$' || !c.is_ascii()
+}
+
+fn is_ident_part(c: char) -> bool {
+    is_ident_start(c) || c.is_ascii_digit()
+}
+
+fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\r' | '\n' | '\u{2028}' | '\u{2029}')
+}
+
+struct Lexer<'a> {
+    source: &'a str,
+    pos: usize,
+    tokens: Vec<Token<'a>>,
+    /// Brace depth at which each enclosing template literal substitution ends.
+    templates: Vec<usize>,
+    braces: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            pos: 0,
+            tokens: vec![],
+            templates: vec![],
+            braces: 0,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.source[self.pos..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.source[self.pos..].chars().nth(1)
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn push(&mut self, kind: Kind<'a>, start: usize) {
+        self.tokens.push(Token {
+            kind,
+            start,
+            end: self.pos,
+        });
+    }
+
+    fn regex_allowed(&self) -> bool {
+        match self.tokens.last().map(|token| &token.kind) {
+            None => true,
+            // `</` is far more likely a JSX closing tag than a comparison with a regex.
+            Some(Kind::Punct(c)) => !matches!(c, ')' | ']' | '}' | '<'),
+            Some(Kind::Ident(name)) => REGEX_KEYWORDS.contains(name),
+            Some(_) => false,
+        }
+    }
+
+    fn tokenize(mut self) -> Vec<Token<'a>> {
+        if self.source.starts_with("#!") {
+            self.skip_line();
+        }
+        while let Some(c) = self.peek() {
+            let start = self.pos;
+            match c {
+                c if c.is_whitespace() => {
+                    self.bump();
+                }
+                '/' if self.peek_second() == Some('/') => self.skip_line(),
+                '/' if self.peek_second() == Some('*') => self.skip_block_comment(),
+                '/' if self.regex_allowed() => {
+                    self.bump();
+                    self.skip_regex();
+                    self.push(Kind::Regex, start);
+                }
+                '\'' | '"' => {
+                    self.bump();
+                    let string = self.read_string(c);
+                    self.push(Kind::Str(string), start);
+                }
+                '`' => {
+                    self.bump();
+                    self.read_template(start);
+                }
+                '{' => {
+                    self.bump();
+                    self.braces += 1;
+                    self.push(Kind::Punct(c), start);
+                }
+                '}' if self.templates.last() == Some(&self.braces) => {
+                    self.bump();
+                    self.templates.pop();
+                    self.read_template(start);
+                }
+                '}' => {
+                    self.bump();
+                    self.braces = self.braces.saturating_sub(1);
+                    self.push(Kind::Punct(c), start);
+                }
+                c if is_ident_part(c) => {
+                    while self.peek().is_some_and(is_ident_part) {
+                        self.bump();
+                    }
+                    self.push(Kind::Ident(&self.source[start..self.pos]), start);
+                }
+                c => {
+                    self.bump();
+                    self.push(Kind::Punct(c), start);
+                }
+            }
+        }
+        self.tokens
+    }
+
+    fn skip_line(&mut self) {
+        while self.peek().is_some_and(|c| !is_line_terminator(c)) {
+            self.bump();
+        }
+    }
+
+    fn skip_block_comment(&mut self) {
+        self.pos += 2;
+        match self.source[self.pos..].find("*/") {
+            Some(end) => self.pos += end + 2,
+            None => self.pos = self.source.len(),
+        }
+    }
+
+    fn skip_regex(&mut self) {
+        let mut in_class = false;
+        while let Some(c) = self.peek() {
+            if is_line_terminator(c) {
+                return;
+            }
+            self.bump();
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '[' => in_class = true,
+                ']' => in_class = false,
+                '/' if !in_class => break,
+                _ => (),
+            }
+        }
+        while self.peek().is_some_and(is_ident_part) {
+            self.bump();
+        }
+    }
+
+    /// Read a string literal up to the closing quote. Unterminated strings end at the end of the
+    /// line, so a stray quote in JSX text only affects a single line.
+    fn read_string(&mut self, quote: char) -> String {
+        let mut string = String::new();
+        while let Some(c) = self.peek() {
+            if is_line_terminator(c) {
+                break;
+            }
+            self.bump();
+            match c {
+                '\\' => self.read_escape(&mut string),
+                c if c == quote => break,
+                c => string.push(c),
+            }
+        }
+        string
+    }
+
+    /// Read the rest of a template literal, starting after a backtick or after the `}` that
+    /// closes a substitution.
+    fn read_template(&mut self, start: usize) {
+        let plain = self.source[start..].starts_with('`');
+        let mut string = String::new();
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => self.read_escape(&mut string),
+                '`' => {
+                    let kind = if plain {
+                        Kind::Str(string)
+                    } else {
+                        Kind::Template
+                    };
+                    self.push(kind, start);
+                    return;
+                }
+                '$' if self.peek() == Some('{') => {
+                    self.bump();
+                    self.templates.push(self.braces);
+                    self.push(Kind::Template, start);
+                    return;
+                }
+                c => string.push(c),
+            }
+        }
+        self.push(Kind::Template, start);
+    }
+
+    fn read_escape(&mut self, string: &mut String) {
+        let c = match self.bump() {
+            Some(c) => c,
+            None => return,
+        };
+        match c {
+            'n' => string.push('\n'),
+            't' => string.push('\t'),
+            'r' => string.push('\r'),
+            'b' => string.push('\u{8}'),
+            'f' => string.push('\u{c}'),
+            'v' => string.push('\u{b}'),
+            '0' => string.push('\0'),
+            'x' => self.read_code_point(2, string),
+            'u' if self.peek() == Some('{') => {
+                self.bump();
+                let digits = self.source[self.pos..].find('}').map_or(0, |end| end + 1);
+                self.read_code_point(digits, string);
+            }
+            'u' => self.read_code_point(4, string),
+            '\r' if self.peek() == Some('\n') => {
+                self.bump();
+            }
+            c if is_line_terminator(c) => (),
+            c => string.push(c),
+        }
+    }
+
+    fn read_code_point(&mut self, len: usize, string: &mut String) {
+        let digits = self
+            .source
+            .get(self.pos..self.pos + len)
+            .map(|digits| digits.trim_end_matches('}'));
+        match digits
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .and_then(std::char::from_u32)
+        {
+            Some(c) => {
+                string.push(c);
+                self.pos += len;
+            }
+            None => string.push('\u{fffd}'),
+        }
+    }
+}
+
+/// The index of the `,` or `)` that ends the call argument starting at token `first`, if the
+/// argument is not empty.
+fn argument_end(tokens: &[Token<'_>], first: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (index, token) in tokens.iter().enumerate().skip(first) {
+        match token.kind {
+            Kind::Punct('(') | Kind::Punct('[') | Kind::Punct('{') => depth += 1,
+            Kind::Punct(')') | Kind::Punct(']') | Kind::Punct('}') if depth > 0 => depth -= 1,
+            Kind::Punct(')') | Kind::Punct(',') if depth == 0 => {
+                return (index > first).then_some(index);
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+/// The tokens of a source, for recovering source text that the ressa AST does not keep positions
+/// for, and for skipping past syntax errors.
+#[cfg(feature = "ressa")]
+pub(crate) struct Tokens<'a> {
+    source: &'a str,
+    tokens: Vec<Token<'a>>,
+}
+
+#[cfg(feature = "ressa")]
+impl<'a> Tokens<'a> {
+    pub(crate) fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            tokens: Lexer::new(source).tokenize(),
+        }
+    }
+
+    /// The text of the first argument of the call whose callee starts at `offset`.
+    pub(crate) fn first_argument(&self, offset: usize) -> Option<&'a str> {
+        let callee = self
+            .tokens
+            .binary_search_by_key(&offset, |token| token.start)
+            .ok()?;
+        if !self.tokens.get(callee + 1)?.is_punct('(') {
+            return None;
+        }
+        let first = callee + 2;
+        let end = argument_end(&self.tokens, first)?;
+        Some(&self.source[self.tokens[first].start..self.tokens[end - 1].end])
+    }
+
+    /// The end of the first `;` at or after `offset`.
+    pub(crate) fn semicolon_after(&self, offset: usize) -> Option<usize> {
+        let first = self.tokens.partition_point(|token| token.start < offset);
+        self.tokens[first..]
+            .iter()
+            .find(|token| token.is_punct(';'))
+            .map(|token| token.end)
+    }
+}
+
+/// Decode the escape sequences in the raw text of a string literal, without its quotes.
+#[cfg(feature = "ressa")]
+pub(crate) fn unescape(raw: &str) -> String {
+    let mut lexer = Lexer::new(raw);
+    let mut string = String::with_capacity(raw.len());
+    while let Some(c) = lexer.bump() {
+        match c {
+            '\\' => lexer.read_escape(&mut string),
+            c => string.push(c),
+        }
+    }
+    string
+}
+
+/// What an open bracket belongs to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Frame {
+    Paren,
+    Bracket,
+    Block,
+    Function,
+    Try,
+    /// The body of an arrow function without braces.
+    Arrow,
+}
+
+/// Keywords whose parenthesized head is followed by a block rather than a function body.
+const BLOCK_KEYWORDS: &[&str] = &["if", "for", "while", "switch", "catch", "with"];
+
+struct Scanner<'a, 'o> {
+    source: &'a str,
+    tokens: Vec<Token<'a>>,
+    /// For each token, whether it is inside a function and whether it is inside a `try` block.
+    contexts: Vec<(bool, bool)>,
+    options: &'o Options,
+    found: Found,
+}
+
+impl<'a, 'o> Scanner<'a, 'o> {
+    fn new(source: &'a str, options: &'o Options) -> Self {
+        let mut scanner = Self {
+            source,
+            tokens: Lexer::new(source).tokenize(),
+            contexts: vec![],
+            options,
+            found: Found::default(),
+        };
+        scanner.contexts = scanner.contexts();
+        scanner
+    }
+
+    fn is_arrow(&self, index: usize) -> bool {
+        index > 0
+            && self.tokens[index].is_punct('>')
+            && self.tokens[index - 1].is_punct('=')
+            && self.tokens[index - 1].end == self.tokens[index].start
+    }
+
+    fn newline_before(&self, index: usize) -> bool {
+        index > 0
+            && self.source[self.tokens[index - 1].end..self.tokens[index].start]
+                .contains(is_line_terminator)
+    }
+
+    /// Guess what a `{` opens from the tokens before it. `paren_head` is the token before the
+    /// `(` of the parenthesized group that was closed last.
+    fn brace_frame(&self, index: usize, paren_head: Option<usize>) -> Frame {
+        let previous = match index.checked_sub(1) {
+            Some(previous) => previous,
+            None => return Frame::Block,
+        };
+        match self.tokens[previous].kind {
+            _ if self.is_arrow(previous) => Frame::Function,
+            Kind::Ident("try") => Frame::Try,
+            Kind::Punct(')') => match paren_head.map(|head| &self.tokens[head].kind) {
+                Some(Kind::Ident(name)) if BLOCK_KEYWORDS.contains(name) => Frame::Block,
+                Some(Kind::Ident(_)) => Frame::Function,
+                _ => Frame::Block,
+            },
+            _ => Frame::Block,
+        }
+    }
+
+    /// Track brackets to tell which tokens are inside functions and `try` blocks. This is a
+    /// heuristic, but it holds up for ordinary code.
+    fn contexts(&self) -> Vec<(bool, bool)> {
+        let mut frames = vec![];
+        let mut paren_heads = vec![];
+        let mut paren_head = None;
+        let mut contexts = Vec::with_capacity(self.tokens.len());
+        for (index, token) in self.tokens.iter().enumerate() {
+            while frames.last() == Some(&Frame::Arrow) {
+                let ends = match token.kind {
+                    Kind::Punct(';' | ',' | ')' | ']' | '}') => true,
+                    _ => self.newline_before(index) && !self.is_arrow(index - 1),
+                };
+                if !ends {
+                    break;
+                }
+                frames.pop();
+            }
+
+            let lazy = frames
+                .iter()
+                .any(|frame| matches!(frame, Frame::Function | Frame::Arrow));
+            contexts.push((lazy, frames.contains(&Frame::Try)));
+
+            match token.kind {
+                Kind::Punct('(') => {
+                    frames.push(Frame::Paren);
+                    paren_heads.push(index.checked_sub(1));
+                }
+                Kind::Punct('[') => frames.push(Frame::Bracket),
+                Kind::Punct('{') => frames.push(self.brace_frame(index, paren_head)),
+                Kind::Punct(')') => {
+                    frames.pop();
+                    paren_head = paren_heads.pop().flatten();
+                }
+                Kind::Punct(']' | '}') => {
+                    frames.pop();
+                }
+                _ if self.is_arrow(index)
+                    && !self.token(index + 1).is_some_and(|t| t.is_punct('{')) =>
+                {
+                    frames.push(Frame::Arrow)
+                }
+                _ => (),
+            }
+        }
+        contexts
+    }
+
+    /// Record the string token at `index` as a dependency.
+    fn add(&mut self, index: usize, kind: RequireKind) {
+        let token = &self.tokens[index];
+        if let Some(string) = token.string() {
+            let (lazy, optional) = self.contexts[index];
+            self.found
+                .add(string.to_string(), kind, token.start, lazy, optional);
+        }
+    }
+
+    fn token(&self, index: usize) -> Option<&Token<'a>> {
+        self.tokens.get(index)
+    }
+
+    fn is_member(&self, index: usize) -> bool {
+        index > 0 && self.tokens[index - 1].is_punct('.')
+    }
+
+    fn scan(mut self) -> Found {
+        for index in 0..self.tokens.len() {
+            if self.is_member(index) {
+                continue;
+            }
+            let name = match self.tokens[index].kind {
+                Kind::Ident(name) => name,
+                _ => continue,
+            };
+            match name {
+                "import" => self.onimport(index),
+                "export" => self.onexport(index),
+                name if name == self.options.word => self.oncall(index),
+                "require" if self.is_import_equals(index) => self.oncall(index),
+                _ => (),
+            }
+        }
+        self.found
+    }
+
+    /// `require('x')`, or `require(expr)` for which the expression text is recorded.
+    fn oncall(&mut self, index: usize) {
+        let after_function = index > 0 && self.tokens[index - 1].is_ident("function");
+        if after_function || !self.token(index + 1).is_some_and(|t| t.is_punct('(')) {
+            return;
+        }
+
+        let first = index + 2;
+        let end = match argument_end(&self.tokens, first) {
+            Some(end) => end,
+            None => return,
+        };
+        // `require(x) { ... }` is a method definition, not a call.
+        let close = self.tokens[end..].iter().position(|t| t.is_punct(')'));
+        if let Some(close) = close {
+            if self.token(end + close + 1).is_some_and(|t| t.is_punct('{')) {
+                return;
+            }
+        }
+
+        match self.tokens[first].string() {
+            Some(_) if end == first + 1 => self.add(first, RequireKind::Require),
+            _ => {
+                let text = &self.source[self.tokens[first].start..self.tokens[end - 1].end];
+                self.found.expressions.push(text.to_string());
+            }
+        }
+    }
+
+    /// TypeScript's `import x = require('x')`.
+    fn is_import_equals(&self, index: usize) -> bool {
+        index >= 3
+            && self.tokens[index - 1].is_punct('=')
+            && matches!(self.tokens[index - 2].kind, Kind::Ident(_))
+            && self.tokens[index - 3].is_ident("import")
+    }
+
+    fn onimport(&mut self, index: usize) {
+        match self.token(index + 1) {
+            // import('x')
+            Some(token) if token.is_punct('(') => {
+                let string = self.token(index + 2).and_then(Token::string);
+                let closed = self.token(index + 3).is_some_and(|t| t.is_punct(')'));
+                if string.is_some() && closed {
+                    self.add(index + 2, RequireKind::DynamicImport);
+                }
+            }
+            // import.meta
+            Some(token) if token.is_punct('.') => (),
+            // import 'x'
+            Some(Token {
+                kind: Kind::Str(_), ..
+            }) => {
+                self.found.goal = Goal::Module;
+                self.add(index + 1, RequireKind::Import);
+            }
+            Some(_) => {
+                self.found.goal = Goal::Module;
+                self.onfrom(index + 1);
+            }
+            None => (),
+        }
+    }
+
+    fn onexport(&mut self, index: usize) {
+        self.found.goal = Goal::Module;
+        self.onfrom(index + 1);
+    }
+
+    /// The `… from 'x'` part of an import or export declaration. Type-only imports and exports
+    /// are skipped, as they do not load anything at runtime.
+    fn onfrom(&mut self, index: usize) {
+        let type_only = self
+            .token(index)
+            .is_some_and(|t| t.is_ident("type") || t.is_ident("typeof"))
+            && !self
+                .token(index + 1)
+                .is_some_and(|t| t.is_ident("from") || t.is_punct(',') || t.is_punct('='));
+        if type_only {
+            return;
+        }
+
+        for i in index..self.tokens.len() {
+            let token = &self.tokens[i];
+            match &token.kind {
+                Kind::Ident("from") => {
+                    if self.token(i + 1).and_then(Token::string).is_some() {
+                        self.add(i + 1, RequireKind::Import);
+                        return;
+                    }
+                }
+                Kind::Ident(_) | Kind::Str(_) => (),
+                Kind::Punct('{') | Kind::Punct('}') | Kind::Punct(',') | Kind::Punct('*') => (),
+                _ => return,
+            }
+        }
+    }
+}
+
+pub(crate) fn find(source: &str, options: &Options) -> Found {
+    let mut found = Scanner::new(source, options).scan();
+    // The goal is only detected for `Goal::Auto`, like the parsers do.
+    if options.goal != Goal::Auto {
+        found.goal = options.goal;
+    }
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Language;
+
+    fn options() -> Options {
+        Options::new().language(Language::TypeScript)
+    }
+
+    #[test]
+    fn jsx() {
+        let found = find(
+            r#"
+            const React = require('react');
+            import Button from './button';
+            export default function App() {
+                const Lazy = React.lazy(() => import('./lazy'));
+                return <div className="app">
+                    <p>Don't panic</p>
+                    <Button onClick={() => require('./click')}>{`count ${n}`}</Button>
+                </div>;
+            }
+        "#,
+            &options(),
+        );
+        assert_eq!(
+            found.strings,
+            vec!["react", "./button", "./lazy", "./click"]
+        );
+        assert_eq!(found.goal, Goal::Module);
+    }
+
+    #[test]
+    fn goal() {
+        let source = "const a = require('a');";
+        assert_eq!(find(source, &options()).goal, Goal::Script);
+        let module = options().goal(Goal::Module);
+        assert_eq!(find(source, &module).goal, Goal::Module);
+        let script = options().goal(Goal::Script);
+        assert_eq!(find("import a from 'a';", &script).goal, Goal::Script);
+    }
+
+    #[test]
+    fn jsx_closing_tag() {
+        let options = Options::new().language(Language::Jsx);
+        let found = find("const a = <a></a>; require('x')", &options);
+        assert_eq!(found.strings, vec!["x"]);
+        let found = find(
+            "const b = <b>{require('y')}</b>, c = require('z');",
+            &options,
+        );
+        assert_eq!(found.strings, vec!["y", "z"]);
+    }
+
+    #[test]
+    fn typescript() {
+        let found = find(
+            r#"
+            import fs = require('fs');
+            import type { Config } from './config';
+            import * as path from "path";
+            import def, { a, b as c } from './named';
+            import './side-effect';
+            export { x } from './x';
+            export * as y from './y';
+            export type { Z } from './z';
+            declare function require(id: string): any;
+            const x: Array<string> = require(`./template`) as string[];
+            function load<T>(name: string): T {
+                return require(name + '.js');
+            }
+        "#,
+            &options(),
+        );
+        assert_eq!(
+            found.strings,
+            vec![
+                "fs",
+                "path",
+                "./named",
+                "./side-effect",
+                "./x",
+                "./y",
+                "./template"
+            ]
+        );
+        assert_eq!(found.expressions, vec!["name + '.js'"]);
+    }
+
+    #[test]
+    fn ignores_comments_and_strings() {
+        let found = find(
+            r#"
+            // require('comment')
+            /* import x from 'block-comment' */
+            var s = "require('string')";
+            var t = `${require('in-template')} require('template')`;
+            var r = /require\('regex'\)/g;
+            obj.require('member');
+            var o = { require(x) { return x } };
+        "#,
+            &options(),
+        );
+        assert_eq!(found.strings, vec!["in-template"]);
+        assert!(found.expressions.is_empty());
+        assert_eq!(found.goal, Goal::Script);
+    }
+
+    #[test]
+    fn word() {
+        let found = find(
+            "import x = require('x');\nload('y');",
+            &options().word("load"),
+        );
+        assert_eq!(found.strings, vec!["x", "y"]);
+    }
+}
@@ -1,22 +1,131 @@
-use resast::prelude::*;
-use ressa::Parser;
-#[cfg(feature = "npm")]
+#[cfg(feature = "serde")]
 use serde_derive::Serialize;
 #[cfg(feature = "npm")]
 use wasm_bindgen::prelude::*;
 
-pub use ressa::Error;
+mod backend;
+mod builtins;
+mod dependencies;
+mod encoding;
+mod error;
+mod glob;
+mod graph;
+mod lexer;
+mod lines;
+mod polyfills;
+mod resolve;
+mod specifier;
+mod walk;
+
+pub use backend::Backend;
+pub use builtins::{
+    builtin_module, builtins, is_builtin, newer_builtins, node_version_for, Builtin, NewerBuiltin,
+    NodeVersion, NODE_PREFIX_SINCE,
+};
+pub use dependencies::{DependencyCheck, Missing, Unused, Usage};
+pub use encoding::decode;
+pub use error::{Error, ErrorKind};
+pub use glob::Glob;
+pub use graph::{
+    Cycle, Edge, EdgeKind, ExportFormat, ExportOptions, Graph, GraphBuilder, Hazard, Metrics,
+    Module, Policy, PublishedFiles, Row, Rule, Rules, Target, Violation,
+};
+pub use polyfills::{browser_builtins, browser_support, BrowserBuiltin, BrowserSupport};
+pub use resolve::{FileSystem, MemoryFileSystem, OsFileSystem, Resolution, ResolveError, Resolver};
+pub use specifier::SpecifierClass;
+pub use walk::Walk;
+
+/// The grammar to parse the source with.
+#[cfg_attr(feature = "npm", wasm_bindgen)]
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "lowercase"))]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Goal {
+    /// A sloppy mode script, like a CommonJS module.
+    Script,
+    /// An ES module, which is always strict and may use `import` and `export`.
+    Module,
+    /// Parse as a script, and retry as a module if the source uses `import` or `export`.
+    #[default]
+    Auto,
+}
+
+impl Goal {
+    /// Parse a goal name: `script`, `module` or `auto`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "script" => Some(Goal::Script),
+            "module" => Some(Goal::Module),
+            "auto" => Some(Goal::Auto),
+            _ => None,
+        }
+    }
+
+    /// The goal implied by a file extension, without the leading dot: `.mjs` files are always
+    /// modules and `.cjs` files are always scripts.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "mjs" | "mts" => Some(Goal::Module),
+            "cjs" | "cts" => Some(Goal::Script),
+            _ => None,
+        }
+    }
+}
+
+/// The language the source is written in. Anything other than plain JavaScript is handled by a
+/// lexical scanner that tolerates type annotations and JSX, instead of by the parser.
+#[cfg_attr(feature = "npm", wasm_bindgen)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    #[default]
+    JavaScript,
+    Jsx,
+    Flow,
+    TypeScript,
+}
+
+impl Language {
+    /// Guess the language from a file extension, without the leading dot.
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "js" | "cjs" | "mjs" => Some(Language::JavaScript),
+            "jsx" => Some(Language::Jsx),
+            "ts" | "cts" | "mts" | "tsx" => Some(Language::TypeScript),
+            _ => None,
+        }
+    }
+
+    /// Parse a language name: `javascript`, `jsx`, `flow` or `typescript`.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "javascript" => Some(Language::JavaScript),
+            "jsx" => Some(Language::Jsx),
+            "flow" => Some(Language::Flow),
+            "typescript" => Some(Language::TypeScript),
+            _ => None,
+        }
+    }
+}
 
 #[cfg_attr(feature = "npm", wasm_bindgen)]
 #[derive(Debug, Clone)]
 pub struct Options {
     word: String,
+    tolerant: bool,
+    goal: Goal,
+    language: Language,
+    backend: Backend,
+    lossy: bool,
 }
 
 impl Default for Options {
     fn default() -> Self {
         Self {
             word: "require".to_string(),
+            tolerant: false,
+            goal: Goal::Auto,
+            language: Language::JavaScript,
+            backend: Backend::default(),
+            lossy: false,
         }
     }
 }
@@ -31,367 +140,161 @@ impl Options {
         self.word = word.to_string();
         self
     }
-}
-
-#[cfg_attr(feature = "npm", derive(Debug, Default, Clone, Serialize))]
-#[cfg_attr(not(feature = "npm"), derive(Debug, Default, Clone))]
-pub struct Found {
-    pub strings: Vec<String>,
-    pub expressions: Vec<String>,
-}
 
-#[derive(Debug)]
-struct Detective<'a> {
-    options: &'a Options,
-    found: Found,
-}
-
-impl<'a> Detective<'a> {
-    fn new(options: &'a Options) -> Self {
-        Self {
-            options,
-            found: Default::default(),
-        }
-    }
-
-    fn check(&mut self, callee: &Expr<'_>, args: &[Expr<'_>]) -> bool {
-        if let Expr::Ident(ref ident) = callee {
-            if ident.name == self.options.word {
-                match args.get(0) {
-                    Some(Expr::Lit(Lit::String(string))) => {
-                        self.found.strings.push(string.clone_inner().to_string());
-                        return true;
-                    }
-                    Some(Expr::Lit(Lit::Template(template))) if template.expressions.is_empty() => {
-                        self.found
-                            .strings
-                            .push(template.quasis[0].cooked.to_string());
-                        return true;
-                    }
-                    Some(_expr) => {
-                        // somehow get the text for this
-                        // self.found.expressions.push()
-                        return true;
-                    }
-                    _ => (),
-                }
-            }
-        }
-        false
+    /// Keep going after syntax errors, collecting them in `Found::diagnostics` instead of failing.
+    pub fn tolerant(mut self, tolerant: bool) -> Self {
+        self.tolerant = tolerant;
+        self
     }
 
-    fn oncall(&mut self, call: &CallExpr<'_>) {
-        if self.check(&*call.callee, &call.arguments) {
-            return;
-        }
-
-        self.onexpr(&*call.callee);
-        for arg in &call.arguments {
-            self.onexpr(arg);
-        }
+    pub fn goal(mut self, goal: Goal) -> Self {
+        self.goal = goal;
+        self
     }
 
-    fn ontemplate(&mut self, tpl: &TemplateLit<'_>) {
-        for expr in &tpl.expressions {
-            self.onexpr(expr);
-        }
+    pub fn language(mut self, language: Language) -> Self {
+        self.language = language;
+        self
     }
 
-    fn onprop(&mut self, prop: &Prop) {
-        match &prop.key {
-            PropKey::Expr(expr) => self.onexpr(expr),
-            PropKey::Pat(pat) => self.onpat(&pat),
-            _ => (),
-        }
-        match &prop.value {
-            PropValue::Expr(expr) => self.onexpr(expr),
-            PropValue::Pat(pat) => self.onpat(&pat),
-            _ => (),
-        }
+    /// Replace invalid UTF-8 by U+FFFD in `find_bytes`, instead of failing.
+    pub fn lossy(mut self, lossy: bool) -> Self {
+        self.lossy = lossy;
+        self
     }
+}
 
-    fn onparams(&mut self, params: &[FuncArg<'_>]) {
-        for param in params {
-            match param {
-                FuncArg::Expr(expr) => self.onexpr(expr),
-                FuncArg::Pat(pat) => self.onpat(&pat),
-            }
-        }
+// wasm-bindgen cannot export an enum whose variants depend on cargo features, so the backend
+// can only be picked from Rust.
+impl Options {
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
     }
+}
 
-    fn onclass(&mut self, class: &Class<'_>) {
-        if let Some(super_class) = &class.super_class {
-            self.onexpr(&*super_class);
-        }
-        for prop in class.body.0.iter() {
-            self.onprop(prop);
-        }
-    }
+/// The syntax a dependency is loaded with.
+#[cfg_attr(feature = "serde", derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RequireKind {
+    /// A call to `require()`, or to the function named by `Options::word`.
+    Require,
+    /// An `import` declaration or an `export … from` declaration.
+    Import,
+    /// A dynamic `import()` call.
+    DynamicImport,
+}
 
-    fn onexpr(&mut self, expr: &Expr<'_>) {
-        match expr {
-            Expr::Array(elements) => {
-                for element in elements {
-                    if let Some(el) = element {
-                        self.onexpr(el);
-                    }
-                }
-            }
-            Expr::ArrowFunc(arrow) => {
-                self.onparams(&arrow.params);
-                match &arrow.body {
-                    ArrowFuncBody::FuncBody(body) => self.onbody(&body.0),
-                    ArrowFuncBody::Expr(expr) => self.onexpr(&*expr),
-                }
-            }
-            Expr::Assign(assign) => {
-                match &assign.left {
-                    AssignLeft::Pat(pat) => self.onpat(&pat),
-                    AssignLeft::Expr(expr) => self.onexpr(&*expr),
-                }
-                self.onexpr(&*assign.right);
-            }
-            Expr::Await(expr) => self.onexpr(&*expr),
-            Expr::Binary(binary) => {
-                self.onexpr(&*binary.left);
-                self.onexpr(&*binary.right);
-            }
-            Expr::Class(class) => self.onclass(class),
-            Expr::Call(call) => self.oncall(call),
-            Expr::Conditional(cond) => {
-                self.onexpr(&*cond.test);
-                self.onexpr(&*cond.consequent);
-                self.onexpr(&*cond.alternate);
-            }
-            Expr::Func(func) => {
-                self.onparams(&func.params);
-                self.onbody(&func.body.0);
-            }
-            Expr::Logical(op) => {
-                self.onexpr(&*op.left);
-                self.onexpr(&*op.right);
-            }
-            Expr::Member(member) => {
-                self.onexpr(&*member.object);
-                self.onexpr(&*member.property);
-            }
-            Expr::New(new) => {
-                if self.check(&*new.callee, &new.arguments) {
-                    return;
-                }
-                self.onexpr(&*new.callee);
-                for arg in &new.arguments {
-                    self.onexpr(arg);
-                }
-            }
-            Expr::Obj(obj) => {
-                for prop in obj.iter() {
-                    match prop {
-                        ObjProp::Prop(prop) => self.onprop(prop),
-                        ObjProp::Spread(expr) => self.onexpr(expr),
-                    }
-                }
-            }
-            Expr::Sequence(seq) => {
-                for expr in seq {
-                    self.onexpr(expr);
-                }
-            }
-            Expr::Spread(expr) => self.onexpr(&*expr),
-            Expr::TaggedTemplate(template) => {
-                self.onexpr(&*template.tag);
-                self.ontemplate(&template.quasi);
-            }
-            Expr::Unary(expr) => self.onexpr(&*expr.argument),
-            Expr::Update(expr) => self.onexpr(&*expr.argument),
-            Expr::Yield(expr) if expr.argument.is_some() => {
-                self.onexpr(&*expr.argument.as_ref().unwrap())
-            }
-            _ => (),
-        }
-    }
+/// A dependency found in the source, with its location.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Require {
+    pub specifier: String,
+    /// What the specifier refers to.
+    pub class: SpecifierClass,
+    pub kind: RequireKind,
+    /// Byte offset of the specifier string literal.
+    pub offset: usize,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in bytes.
+    pub column: usize,
+    /// Inside a function, so it only runs when the function is called.
+    pub lazy: bool,
+    /// Inside a `try` block, so a failure to load it may be handled.
+    pub optional: bool,
+}
 
-    fn onpat(&mut self, pat: &Pat<'_>) {
-        match pat {
-            Pat::Obj(obj) => {
-                for part in obj.iter() {
-                    match part {
-                        ObjPatPart::Assign(prop) => self.onprop(prop),
-                        ObjPatPart::Rest(pat) => self.onpat(&*pat),
-                    }
-                }
-            }
-            Pat::Array(array) => {
-                for part in array.iter() {
-                    match part {
-                        Some(ArrayPatPart::Pat(pat)) => self.onpat(pat),
-                        Some(ArrayPatPart::Expr(expr)) => self.onexpr(expr),
-                        _ => (),
-                    }
-                }
-            }
-            Pat::RestElement(rest) => self.onpat(&*rest),
-            Pat::Assign(assign) => {
-                self.onpat(&*assign.left);
-                self.onexpr(&*assign.right);
-            }
-            _ => (),
-        }
-    }
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone)]
+pub struct Found {
+    pub strings: Vec<String>,
+    pub expressions: Vec<String>,
+    /// The same dependencies as `strings`, in the same order, with more detail.
+    pub requires: Vec<Require>,
+    pub diagnostics: Vec<Error>,
+    /// The goal the source was parsed with; never `Goal::Auto`.
+    pub goal: Goal,
+}
 
-    fn onvar(&mut self, decls: &[VarDecl]) {
-        for decl in decls.iter() {
-            self.onpat(&decl.id);
-            if let Some(init) = &decl.init {
-                self.onexpr(init);
-            }
+impl Default for Found {
+    fn default() -> Self {
+        Self {
+            strings: vec![],
+            expressions: vec![],
+            requires: vec![],
+            diagnostics: vec![],
+            goal: Goal::Script,
         }
     }
+}
 
-    fn ondecl(&mut self, decl: &Decl) {
-        match decl {
-            Decl::Var(_, decls) => self.onvar(&decls),
-            Decl::Func(func) => {
-                self.onparams(&func.params);
-                self.onbody(&func.body.0);
-            }
-            Decl::Class(class) => self.onclass(class),
-            Decl::Import(_) => (),
-            Decl::Export(export) => match &**export {
-                ModExport::Default(DefaultExportDecl::Decl(decl)) => self.ondecl(decl),
-                ModExport::Default(DefaultExportDecl::Expr(expr)) => self.onexpr(expr),
-                ModExport::Named(NamedExportDecl::Decl(decl)) => self.ondecl(decl),
-                _ => (),
-            },
-        };
-    }
-
-    fn onloopleft(&mut self, left: &LoopLeft<'_>) {
-        match &left {
-            LoopLeft::Expr(expr) => self.onexpr(expr),
-            LoopLeft::Variable(_, decl) => {
-                self.onpat(&decl.id);
-                if let Some(init) = &decl.init {
-                    self.onexpr(init);
-                }
-            }
-            LoopLeft::Pat(pat) => self.onpat(pat),
+impl Found {
+    pub(crate) fn add(
+        &mut self,
+        specifier: String,
+        kind: RequireKind,
+        offset: usize,
+        lazy: bool,
+        optional: bool,
+    ) {
+        self.strings.push(specifier.clone());
+        self.requires.push(Require {
+            class: SpecifierClass::parse(&specifier),
+            specifier,
+            kind,
+            offset,
+            line: 0,
+            column: 0,
+            lazy,
+            optional,
+        });
+    }
+
+    /// Fill in the line and column of each require.
+    pub(crate) fn locate(mut self, source: &str) -> Self {
+        let index = lines::LineIndex::new(source);
+        for require in &mut self.requires {
+            let (line, column) = index.position(require.offset);
+            require.line = line;
+            require.column = column;
         }
+        self
     }
+}
 
-    fn onstmt(&mut self, stmt: &Stmt<'_>) {
-        match stmt {
-            Stmt::Expr(expr) => self.onexpr(expr),
-            Stmt::Block(block) => self.onbody(&block.0),
-            Stmt::With(with) => {
-                self.onexpr(&with.object);
-                self.onstmt(&*with.body);
-            }
-            Stmt::Return(Some(expr)) => self.onexpr(expr),
-            Stmt::Labeled(label) => self.onstmt(&*label.body),
-            Stmt::If(stmt) => {
-                self.onexpr(&stmt.test);
-                self.onstmt(&*stmt.consequent);
-                if let Some(alternate) = &stmt.alternate {
-                    self.onstmt(&*alternate);
-                }
-            }
-            Stmt::Switch(switch) => {
-                self.onexpr(&switch.discriminant);
-                for case in &switch.cases {
-                    if let Some(expr) = &case.test {
-                        self.onexpr(expr);
-                    }
-                    self.onbody(&case.consequent);
-                }
-            }
-            Stmt::Throw(err) => self.onexpr(err),
-            Stmt::Try(stmt) => {
-                self.onbody(&stmt.block.0);
-                if let Some(catch) = &stmt.handler {
-                    if let Some(pat) = &catch.param {
-                        self.onpat(pat);
-                    }
-                    self.onbody(&catch.body.0);
-                }
-                if let Some(finalizer) = &stmt.finalizer {
-                    self.onbody(&finalizer.0);
-                }
-            }
-            Stmt::While(stmt) => {
-                self.onexpr(&stmt.test);
-                self.onstmt(&*stmt.body);
-            }
-            Stmt::DoWhile(stmt) => {
-                self.onstmt(&*stmt.body);
-                self.onexpr(&stmt.test);
-            }
-            Stmt::For(stmt) => {
-                match &stmt.init {
-                    Some(LoopInit::Variable(_, decls)) => self.onvar(&decls),
-                    Some(LoopInit::Expr(expr)) => self.onexpr(expr),
-                    _ => (),
-                }
-                if let Some(test) = &stmt.test {
-                    self.onexpr(test);
-                }
-                if let Some(update) = &stmt.update {
-                    self.onexpr(update);
-                }
-                self.onstmt(&*stmt.body);
-            }
-            Stmt::ForIn(stmt) => {
-                self.onloopleft(&stmt.left);
-                self.onexpr(&stmt.right);
-                self.onstmt(&*stmt.body)
-            }
-            Stmt::ForOf(stmt) => {
-                self.onloopleft(&stmt.left);
-                self.onexpr(&stmt.right);
-                self.onstmt(&*stmt.body)
-            }
-            Stmt::Var(var) => self.onvar(&var),
-            _ => (),
-        }
+pub fn find(source: &str, options: &Options) -> Result<Found, Error> {
+    if options.language != Language::JavaScript {
+        return Ok(lexer::find(source, options).locate(source));
     }
 
-    fn onbody(&mut self, body: &[ProgramPart]) {
-        for part in body {
-            match part {
-                ProgramPart::Decl(decl) => self.ondecl(decl),
-                ProgramPart::Stmt(stmt) => self.onstmt(stmt),
-                _ => (),
-            }
-        }
+    // Scripts can use `import()` too, but not `export`.
+    let may_import =
+        source.contains("import") || (options.goal != Goal::Script && source.contains("export"));
+    if !source.contains(&options.word) && !may_import {
+        let goal = match options.goal {
+            Goal::Auto => Goal::Script,
+            goal => goal,
+        };
+        return Ok(Found {
+            goal,
+            ..Default::default()
+        });
     }
 
-    fn find(mut self, source: &str) -> Result<Found, Error> {
-        if !source.contains(&self.options.word) {
-            return Ok(self.found);
-        }
-
-        let parser = Parser::new(source)?;
-        for part in parser {
-            match &part? {
-                ProgramPart::Decl(decl) => self.ondecl(decl),
-                ProgramPart::Stmt(stmt) => self.onstmt(stmt),
-                _ => (),
-            }
-        }
-
-        Ok(self.found)
-    }
+    backend::find(source, options).map(|found| found.locate(source))
 }
 
-pub fn find(source: &str, options: &Options) -> Result<Found, Error> {
-    Detective::new(options).find(source)
+/// Like `find`, but for the raw contents of a file. See `decode` for how the bytes are decoded.
+pub fn find_bytes(source: &[u8], options: &Options) -> Result<Found, Error> {
+    find(&decode(source, options)?, options)
 }
 
 pub fn detective(source: &str, options: &Options) -> Result<Vec<String>, Error> {
-    Detective::new(options).find(source).map(|res| res.strings)
+    find(source, options).map(|res| res.strings)
 }
 
-#[cfg(garget_arch = "wasm32")]
+#[cfg(all(target_arch = "wasm32", feature = "npm"))]
 pub use wasm::*;
 
 #[cfg(feature = "npm")]
@@ -400,7 +303,7 @@ mod wasm {
     use super::*;
 
     fn convert_err(err: Error) -> JsValue {
-        JsValue::from_str(&format!("{}", err))
+        JsValue::from_serde(&err).unwrap()
     }
 
     #[wasm_bindgen(js_name = "find")]
@@ -422,6 +325,38 @@ mod wasm {
 mod tests {
     use super::*;
 
+    /// Run `find` with every backend that is compiled in, and check that they agree.
+    fn find(source: &str, options: &Options) -> Result<Found, Error> {
+        let mut results = Backend::all().iter().map(|&backend| {
+            let result = super::find(source, &options.clone().backend(backend));
+            (backend, result)
+        });
+        let (_, first) = results.next().unwrap();
+        for (backend, result) in results {
+            match (&first, &result) {
+                (Ok(expected), Ok(actual)) => {
+                    assert_eq!(actual.strings, expected.strings, "{:?} strings", backend);
+                    assert_eq!(
+                        actual.expressions, expected.expressions,
+                        "{:?} expressions",
+                        backend
+                    );
+                    // Backends recover from syntax errors differently, which can lose context.
+                    if expected.diagnostics.is_empty() {
+                        assert_eq!(actual.requires, expected.requires, "{:?} requires", backend);
+                    }
+                    assert_eq!(actual.goal, expected.goal, "{:?} goal", backend);
+                }
+                (Err(_), Err(_)) => (),
+                _ => panic!(
+                    "{:?} backend returned {:?}, expected {:?}",
+                    backend, result, first
+                ),
+            }
+        }
+        first
+    }
+
     #[test]
     fn both() {
         let found = find(
@@ -439,6 +374,42 @@ mod tests {
         assert_eq!(found.expressions, vec!["'c' + x", "'d' + y"]);
     }
 
+    #[test]
+    fn requires() {
+        let source = "import a from 'a';\n\
+                      const b = () => require('b');\n\
+                      try {\n  require(`c`);\n} catch {}\n\
+                      function d() {\n  if (x) { import('d') }\n}\n\
+                      require('e');";
+        let summary = |found: Found| {
+            found
+                .requires
+                .into_iter()
+                .map(|r| (r.specifier, r.kind, r.line, r.column, r.lazy, r.optional))
+                .collect::<Vec<_>>()
+        };
+        let expected = vec![
+            ("a".to_string(), RequireKind::Import, 1, 15, false, false),
+            ("b".to_string(), RequireKind::Require, 2, 25, true, false),
+            ("c".to_string(), RequireKind::Require, 4, 11, false, true),
+            (
+                "d".to_string(),
+                RequireKind::DynamicImport,
+                7,
+                19,
+                true,
+                false,
+            ),
+            ("e".to_string(), RequireKind::Require, 9, 9, false, false),
+        ];
+        let found = find(source, &Default::default()).unwrap();
+        assert_eq!(found.requires[0].offset, 14);
+        assert_eq!(summary(found), expected);
+
+        let typescript = Options::new().language(Language::TypeScript);
+        assert_eq!(summary(super::find(source, &typescript).unwrap()), expected);
+    }
+
     #[test]
     fn chained() {
         let found = find(
@@ -644,6 +615,40 @@ mod tests {
         assert!(found.expressions.is_empty());
     }
 
+    #[test]
+    fn top_level_return_in_block() {
+        let found = find(
+            r#"
+            if (module.parent) return require('a')(x)
+            else if (y) { return; }
+            require(b ? 'c' : 'd');
+        "#,
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(found.strings, vec!["a"]);
+        assert_eq!(found.expressions, vec!["b ? 'c' : 'd'"]);
+        assert_eq!(found.requires[0].column, 47);
+    }
+
+    #[test]
+    fn top_level_return_after_non_ascii() {
+        let found = find("var s = 'ééééé'; return require('a')", &Default::default()).unwrap();
+        assert_eq!(found.strings, vec!["a"]);
+        assert_eq!(found.requires[0].column, 38);
+    }
+
+    #[test]
+    fn escapes() {
+        let found = find(
+            r#"require('\x61'); require("b\u{2F}c");"#,
+            &Default::default(),
+        )
+        .unwrap();
+        assert_eq!(found.strings, vec!["a", "b/c"]);
+        assert_eq!(found.requires[0].column, 9);
+    }
+
     #[test]
     fn set_in_object_pat() {
         let found = find(
@@ -768,4 +773,125 @@ mod tests {
         assert_eq!(found.strings, vec!["a", "c"]);
         assert!(found.expressions.is_empty());
     }
+
+    #[test]
+    fn tolerant() {
+        let source = r#"
+            var a = require('a');
+            function broken () {
+                var b = require('b');
+                var = 10;
+            }
+            var c = require('c');
+        "#;
+        assert!(find(source, &Default::default()).is_err());
+
+        let found = find(source, &Options::new().tolerant(true)).unwrap();
+        assert_eq!(found.strings, vec!["a", "b", "c"]);
+        assert!(!found.diagnostics.is_empty());
+        assert_eq!(found.diagnostics[0].line, 5);
+    }
+
+    /// ressa restarts after the statement with the error. oxc recovers its own way.
+    #[cfg(feature = "ressa")]
+    #[test]
+    fn tolerant_same_line() {
+        let options = Options::new()
+            .word("load")
+            .tolerant(true)
+            .backend(Backend::Ressa);
+        let found = super::find("load('z'); var = 1; load('w')", &options).unwrap();
+        assert_eq!(found.strings, vec!["z", "w"]);
+        assert_eq!(found.diagnostics.len(), 1);
+    }
+
+    #[test]
+    fn tolerant_last_line() {
+        let found = find(
+            "var a = require('a');\nvar = 1",
+            &Options::new().tolerant(true),
+        )
+        .unwrap();
+        assert_eq!(found.strings, vec!["a"]);
+        assert_eq!(found.diagnostics.len(), 1);
+        assert_eq!(found.diagnostics[0].line, 2);
+    }
+
+    #[test]
+    fn tolerant_unclosed() {
+        let source = "require('a');\nfunction f() {\nrequire('b');";
+        let found = find(source, &Options::new().tolerant(true)).unwrap();
+        assert_eq!(found.strings, vec!["a", "b"]);
+        assert!(!found.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn non_ascii_error() {
+        let source = "var s = '日日日日日日日'; var = require('a')";
+        let error = find(source, &Default::default()).unwrap_err();
+        assert_eq!((error.line, error.column), (1, 38));
+
+        let found = find(source, &Options::new().tolerant(true)).unwrap();
+        assert_eq!(found.diagnostics[0].column, 38);
+    }
+
+    #[test]
+    fn tolerant_valid_source() {
+        let found = find(
+            r#"
+            var a = require('a');
+            var b = require('b');
+        "#,
+            &Options::new().tolerant(true),
+        )
+        .unwrap();
+        assert_eq!(found.strings, vec!["a", "b"]);
+        assert!(found.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn goal() {
+        let source = r#"
+            import a from 'a';
+            export { b } from 'b';
+            export * from 'c';
+            var d = require('d');
+        "#;
+
+        let found = find(source, &Default::default()).unwrap();
+        assert_eq!(found.goal, Goal::Module);
+        assert_eq!(found.strings, vec!["a", "b", "c", "d"]);
+
+        let found = find(source, &Options::new().goal(Goal::Module)).unwrap();
+        assert_eq!(found.goal, Goal::Module);
+        assert_eq!(found.strings, vec!["a", "b", "c", "d"]);
+
+        let error = find(source, &Options::new().goal(Goal::Script)).unwrap_err();
+        assert_eq!(error.kind, ErrorKind::ModuleSyntax);
+
+        let found = find(source, &Options::new().tolerant(true)).unwrap();
+        assert_eq!(found.goal, Goal::Module);
+        assert_eq!(found.strings, vec!["a", "b", "c", "d"]);
+        assert!(found.diagnostics.is_empty());
+    }
+
+    #[test]
+    fn goal_script() {
+        let source = r#"
+            with (obj) {
+                var a = require('a');
+            }
+        "#;
+
+        let found = find(source, &Default::default()).unwrap();
+        assert_eq!(found.goal, Goal::Script);
+        assert_eq!(found.strings, vec!["a"]);
+    }
+
+    #[test]
+    fn goal_script_dynamic_import() {
+        let found = find("import('./a');", &Options::new().goal(Goal::Script)).unwrap();
+        assert_eq!(found.goal, Goal::Script);
+        assert_eq!(found.strings, vec!["./a"]);
+    }
 }
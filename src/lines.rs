@@ -0,0 +1,141 @@
+fn is_line_terminator(c: char) -> bool {
+    matches!(c, '\r' | '\n' | '\u{2028}' | '\u{2029}')
+}
+
+/// Byte offsets just past each line terminator, using the same line terminators as ressa.
+fn line_ends(source: &str) -> impl Iterator<Item = usize> + '_ {
+    let mut chars = source.char_indices().peekable();
+    std::iter::from_fn(move || {
+        while let Some((index, c)) = chars.next() {
+            if c == '\r' && chars.peek().map(|&(_, next)| next) == Some('\n') {
+                continue;
+            }
+            if is_line_terminator(c) {
+                return Some(index + c.len_utf8());
+            }
+        }
+        None
+    })
+}
+
+/// Byte offset of the start of the 1-based `line`.
+pub(crate) fn line_start(source: &str, line: usize) -> Option<usize> {
+    if line <= 1 {
+        return Some(0);
+    }
+    line_ends(source).nth(line - 2)
+}
+
+/// 1-based line and byte column for a byte offset.
+pub(crate) fn position(source: &str, offset: usize) -> (usize, usize) {
+    let mut line = 1;
+    let mut start = 0;
+    for end in line_ends(source) {
+        if end > offset {
+            break;
+        }
+        line += 1;
+        start = end;
+    }
+    (line, offset - start + 1)
+}
+
+/// Line starts of a source, for looking up many positions quickly.
+pub(crate) struct LineIndex {
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub(crate) fn new(source: &str) -> Self {
+        Self {
+            starts: std::iter::once(0).chain(line_ends(source)).collect(),
+        }
+    }
+
+    /// 1-based line and byte column for a byte offset, like `position`.
+    pub(crate) fn position(&self, offset: usize) -> (usize, usize) {
+        let line = self.starts.partition_point(|&start| start <= offset);
+        (line, offset - self.starts[line - 1] + 1)
+    }
+}
+
+/// Byte offset for a 1-based line and column counted in characters, like ressa counts them,
+/// clamped to the end of the source.
+#[cfg_attr(not(feature = "ressa"), allow(dead_code))]
+pub(crate) fn offset(source: &str, line: usize, column: usize) -> usize {
+    let start = match line_start(source, line) {
+        Some(start) => start,
+        None => return source.len(),
+    };
+    source[start..]
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map_or(source.len(), |(index, _)| start + index)
+}
+
+/// The text of the 1-based `line`, without its line terminator.
+pub(crate) fn line_text(source: &str, line: usize) -> Option<&str> {
+    let start = line_start(source, line)?;
+    let rest = &source[start..];
+    let end = rest.find(is_line_terminator).unwrap_or(rest.len());
+    Some(&rest[..end])
+}
+
+/// Replace everything before `offset` by spaces, keeping line terminators, so that offsets and
+/// positions reported for the rest of the source stay the same.
+#[cfg_attr(not(feature = "ressa"), allow(dead_code))]
+pub(crate) fn blank_before(source: &str, offset: usize) -> String {
+    let mut blanked = String::with_capacity(source.len());
+    for c in source[..offset].chars() {
+        if is_line_terminator(c) {
+            blanked.push(c);
+        } else {
+            blanked.extend(std::iter::repeat_n(' ', c.len_utf8()));
+        }
+    }
+    blanked.push_str(&source[offset..]);
+    blanked
+}
+
+/// Replace the contents of the 1-based `line` by spaces. Returns `None` if the line is already
+/// blank or does not exist.
+#[cfg(feature = "oxc")]
+pub(crate) fn blank_line(source: &str, line: usize) -> Option<String> {
+    let start = line_start(source, line)?;
+    let text = line_text(source, line)?;
+    if text.trim().is_empty() {
+        return None;
+    }
+    let mut blanked = source.to_string();
+    blanked.replace_range(start..start + text.len(), &" ".repeat(text.len()));
+    Some(blanked)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions() {
+        let source = "a\nbc\r\nd\re\u{2028}f";
+        assert_eq!(line_start(source, 1), Some(0));
+        assert_eq!(line_start(source, 2), Some(2));
+        assert_eq!(line_start(source, 3), Some(6));
+        assert_eq!(line_start(source, 4), Some(8));
+        assert_eq!(line_start(source, 5), Some(12));
+        assert_eq!(line_start(source, 6), None);
+        assert_eq!(position(source, 3), (2, 2));
+        assert_eq!(position(source, 6), (3, 1));
+        assert_eq!(offset(source, 2, 2), 3);
+        assert_eq!(offset("é\nbé=", 2, 3), 6);
+        assert_eq!(offset("é\nbé=", 2, 9), 7);
+        assert_eq!(line_text(source, 2), Some("bc"));
+        assert_eq!(line_text(source, 5), Some("f"));
+        assert_eq!(blank_before(source, 6), " \n  \r\nd\re\u{2028}f");
+
+        let index = LineIndex::new(source);
+        for offset in 0..source.len() {
+            assert_eq!(index.position(offset), position(source, offset));
+        }
+    }
+}
@@ -1,28 +1,1267 @@
-use require_detective::detective;
+use require_detective::{
+    browser_builtins, decode, find, newer_builtins, Backend, BrowserBuiltin, BrowserSupport,
+    DependencyCheck, EdgeKind, Error, ExportFormat, ExportOptions, Found, Glob, Goal, Graph,
+    GraphBuilder, Hazard, Language, Metrics, NewerBuiltin, NodeVersion, Options, PublishedFiles,
+    Resolver, Rule, Rules, Target, Walk,
+};
+use serde_derive::Serialize;
+use std::collections::HashSet;
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+
+/// The path argument that reads from stdin.
+const STDIN: &str = "-";
+
+/// Bumped whenever the JSON output changes in a way that is not backwards compatible.
+const SCHEMA_VERSION: u32 = 1;
+
+const USAGE: &str = "usage: require-detective [command] [options] <path>...
+
+Paths can be files, directories or glob patterns like 'src/**/*.js'. Use '-', or no paths, to read
+from stdin.
+
+commands:
+  cycles               report circular dependencies between the modules reachable from the given
+                       entry files, and exit with 1 if any of them are eager
+  why <module>         print the shortest chain of requires from each entry file to a module,
+                       given as a file path or a package name
+  dependencies         compare the packages required by the files in a package directory
+                       (default: .) with its package.json, and exit with 1 if any are unused
+                       or undeclared
+  builtins             report requires of Node core modules that need a newer Node than
+                       --node, and exit with 1 if there are any
+  polyfills            report which required Node core modules work in browsers, with a shim
+                       or polyfill package, and exit with 1 if any have no browser equivalent
+  unreachable          list the files in the working directory that the given entry files never
+                       require, and exit with 1 if there are any. Exits with 1 without listing
+                       anything if a module cannot be read, parsed or resolved
+  check                check the dependencies reachable from the given entry files against the
+                       --rules file, and exit with 1 if any break a rule. Requires that cannot
+                       be resolved are reported, but do not fail the check
+  metrics              print the fan-in, fan-out, transitive dependencies and size, depth and
+                       instability of each module reachable from the given entry files
+
+options:
+  --include <glob>     only scan files in directories that match the glob
+  --exclude <glob>     skip files and directories that match the glob. For unreachable, skip
+                       them as candidates
+  --ext <ext>          scan files in directories with this extension (default: js, cjs, mjs)
+  --format <format>    output format: text (default), json or ndjson. These formats walk the
+                       dependency graph from the given entry files instead:
+                       deps      module-deps rows
+                       dot       a Graphviz graph
+                       mermaid   a Mermaid flowchart
+                       graphml   a GraphML document
+  --browser            resolve with the package.json browser field, like browserify
+  --collapse-packages  draw each package in node_modules as one node
+  --color-edges        color edges by kind: static, dynamic, lazy or optional
+  --word <name>        the name of the require function (default: require)
+  --tolerant           report syntax errors on stderr and keep going
+  --goal <goal>        parse as script, module or auto (default: auto)
+  --language <lang>    javascript, jsx, flow or typescript (default: from the file extension)
+  --backend <backend>  the parser to use: ressa or oxc, if compiled in
+  --lossy              replace invalid UTF-8 instead of failing
+  --config <file>      read options from a JSON file, with the option names as keys
+  --node <version>     the oldest supported Node version for builtins (default: engines.node
+                       in ./package.json)
+  --ignore <glob>      leave packages matching the glob out of the dependencies report
+  --rules <file>       the JSON file with the dependency rules for check
+  --sort <column>      sort metrics by file, fan-in, fan-out, transitive, size (default), depth
+                       or instability. Numbers are sorted in descending order
+  --filename <name>    the file name to report for stdin, and to pick the language and goal by";
 
 fn err(message: &str) -> ! {
     eprintln!("{}", message);
     std::process::exit(1)
 }
 
-fn main() {
-    let file = match std::env::args().nth(1) {
-        Some(file) => file,
-        None => err("missing file arg"),
+/// What to do with the files. Without a command, their dependencies are printed.
+#[derive(Default, Clone, PartialEq, Eq)]
+enum Command {
+    #[default]
+    Scan,
+    Cycles,
+    /// The file or package name to explain.
+    Why(String),
+    Dependencies,
+    Builtins,
+    Polyfills,
+    Unreachable,
+    Check,
+    Metrics,
+}
+
+impl Command {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "cycles" => Some(Command::Cycles),
+            "dependencies" => Some(Command::Dependencies),
+            "builtins" => Some(Command::Builtins),
+            "polyfills" => Some(Command::Polyfills),
+            "unreachable" => Some(Command::Unreachable),
+            "check" => Some(Command::Check),
+            "metrics" => Some(Command::Metrics),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    #[default]
+    Text,
+    Json,
+    Ndjson,
+    /// Rows in the shape of browserify's module-deps.
+    Deps,
+    Export(ExportFormat),
+}
+
+impl Format {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "text" => Some(Format::Text),
+            "json" => Some(Format::Json),
+            "ndjson" => Some(Format::Ndjson),
+            "deps" => Some(Format::Deps),
+            name => ExportFormat::from_name(name).map(Format::Export),
+        }
+    }
+}
+
+/// The column to sort the metrics report by.
+#[derive(Default, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    File,
+    FanIn,
+    FanOut,
+    Transitive,
+    /// The transitive size.
+    #[default]
+    Size,
+    Depth,
+    Instability,
+}
+
+impl SortKey {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "file" => Some(SortKey::File),
+            "fan-in" => Some(SortKey::FanIn),
+            "fan-out" => Some(SortKey::FanOut),
+            "transitive" => Some(SortKey::Transitive),
+            "size" => Some(SortKey::Size),
+            "depth" => Some(SortKey::Depth),
+            "instability" => Some(SortKey::Instability),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Args {
+    command: Command,
+    paths: Vec<String>,
+    config: Option<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    ignore: Vec<String>,
+    extensions: Vec<String>,
+    format: Option<Format>,
+    word: Option<String>,
+    tolerant: Option<bool>,
+    lossy: Option<bool>,
+    browser: Option<bool>,
+    collapse_packages: Option<bool>,
+    color_edges: Option<bool>,
+    goal: Option<Goal>,
+    language: Option<Language>,
+    backend: Option<Backend>,
+    filename: Option<String>,
+    node: Option<NodeVersion>,
+    rules: Option<String>,
+    sort: Option<SortKey>,
+}
+
+fn parse_name<T>(kind: &str, name: &str, parse: fn(&str) -> Option<T>) -> Result<T, String> {
+    parse(name).ok_or_else(|| format!("unknown {} {}", kind, name))
+}
+
+fn parse_bool(name: &str, value: &str) -> Result<bool, String> {
+    match value {
+        "true" => Ok(true),
+        "false" => Ok(false),
+        _ => Err(format!("{} must be true or false, got {}", name, value)),
+    }
+}
+
+impl Args {
+    /// Set an option by name. Used for both command line flags and config file keys.
+    fn set(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "include" => self.include.push(value.to_string()),
+            "exclude" => self.exclude.push(value.to_string()),
+            "ignore" => self.ignore.push(value.to_string()),
+            "ext" => self.extensions.push(value.to_string()),
+            "format" => self.format = Some(parse_name("format", value, Format::from_name)?),
+            "word" => self.word = Some(value.to_string()),
+            "tolerant" => self.tolerant = Some(parse_bool(name, value)?),
+            "lossy" => self.lossy = Some(parse_bool(name, value)?),
+            "browser" => self.browser = Some(parse_bool(name, value)?),
+            "collapse-packages" => self.collapse_packages = Some(parse_bool(name, value)?),
+            "color-edges" => self.color_edges = Some(parse_bool(name, value)?),
+            "goal" => self.goal = Some(parse_name("goal", value, Goal::from_name)?),
+            "language" => self.language = Some(parse_name("language", value, Language::from_name)?),
+            "backend" => self.backend = Some(parse_name("backend", value, Backend::from_name)?),
+            "filename" => self.filename = Some(value.to_string()),
+            "node" => self.node = Some(parse_name("Node version", value, NodeVersion::parse)?),
+            "rules" => self.rules = Some(value.to_string()),
+            "sort" => self.sort = Some(parse_name("sort column", value, SortKey::from_name)?),
+            _ => return Err(format!("unknown option {}", name)),
+        }
+        Ok(())
+    }
+
+    /// Use the settings from `config` for everything that was not given on the command line.
+    fn or(self, config: Args) -> Args {
+        let list = |args: Vec<String>, config: Vec<String>| match args.is_empty() {
+            true => config,
+            false => args,
+        };
+        Args {
+            command: self.command,
+            paths: self.paths,
+            config: self.config,
+            include: list(self.include, config.include),
+            exclude: list(self.exclude, config.exclude),
+            ignore: list(self.ignore, config.ignore),
+            extensions: list(self.extensions, config.extensions),
+            format: self.format.or(config.format),
+            word: self.word.or(config.word),
+            tolerant: self.tolerant.or(config.tolerant),
+            lossy: self.lossy.or(config.lossy),
+            browser: self.browser.or(config.browser),
+            collapse_packages: self.collapse_packages.or(config.collapse_packages),
+            color_edges: self.color_edges.or(config.color_edges),
+            goal: self.goal.or(config.goal),
+            language: self.language.or(config.language),
+            backend: self.backend.or(config.backend),
+            filename: self.filename.or(config.filename),
+            node: self.node.or(config.node),
+            rules: self.rules.or(config.rules),
+            sort: self.sort.or(config.sort),
+        }
+    }
+
+    /// The name to report `file` by, which is `--filename` for stdin.
+    fn name(&self, file: &Path) -> PathBuf {
+        match self.filename.as_ref() {
+            _ if file != Path::new(STDIN) => file.to_path_buf(),
+            Some(filename) => PathBuf::from(filename),
+            None => PathBuf::from("<stdin>"),
+        }
+    }
+
+    fn options(&self, file: &Path) -> Options {
+        let extension = file.extension().and_then(|extension| extension.to_str());
+        let mut options = Options::new();
+        if let Some(word) = &self.word {
+            options = options.word(word);
+        }
+        if let Some(tolerant) = self.tolerant {
+            options = options.tolerant(tolerant);
+        }
+        if let Some(lossy) = self.lossy {
+            options = options.lossy(lossy);
+        }
+        if let Some(goal) = self
+            .goal
+            .or_else(|| extension.and_then(Goal::from_extension))
+        {
+            options = options.goal(goal);
+        }
+        if let Some(backend) = self.backend {
+            options = options.backend(backend);
+        }
+        let language = self.language.unwrap_or_else(|| {
+            extension
+                .and_then(Language::from_extension)
+                .unwrap_or_default()
+        });
+        options.language(language)
+    }
+}
+
+fn parse_args(args: impl Iterator<Item = String>) -> Args {
+    let mut parsed = Args::default();
+    let mut args = args.peekable();
+    if let Some(command) = args.peek().and_then(|name| Command::from_name(name)) {
+        parsed.command = command;
+        args.next();
+    } else if args.next_if_eq("why").is_some() {
+        match args.next() {
+            Some(module) => parsed.command = Command::Why(module),
+            None => err(&format!("missing module for why\n\n{}", USAGE)),
+        }
+    }
+    while let Some(arg) = args.next() {
+        let result = match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                std::process::exit(0)
+            }
+            "--tolerant" => parsed.set("tolerant", "true"),
+            "--lossy" => parsed.set("lossy", "true"),
+            "--browser" => parsed.set("browser", "true"),
+            "--collapse-packages" => parsed.set("collapse-packages", "true"),
+            "--color-edges" => parsed.set("color-edges", "true"),
+            "--config" => match args.next() {
+                Some(file) => {
+                    parsed.config = Some(file);
+                    Ok(())
+                }
+                None => Err("missing value for --config".to_string()),
+            },
+            _ if arg.starts_with("--") => match args.next() {
+                Some(value) => parsed.set(&arg[2..], &value),
+                None => Err(format!("missing value for {}", arg)),
+            },
+            _ if arg.starts_with('-') && arg != "-" => Err(format!("unknown option {}", arg)),
+            _ => {
+                parsed.paths.push(arg);
+                Ok(())
+            }
+        };
+        if let Err(message) = result {
+            err(&format!("{}\n\n{}", message, USAGE));
+        }
+    }
+    if parsed.paths.is_empty() && parsed.command == Command::Dependencies {
+        parsed.paths.push(".".to_string());
+    }
+    if parsed.paths.is_empty() {
+        if std::io::stdin().is_terminal() {
+            err(&format!("missing file arg\n\n{}", USAGE));
+        }
+        parsed.paths.push(STDIN.to_string());
+    }
+    parsed
+}
+
+/// Read a JSON config file. Keys are the long option names, values are strings, booleans for
+/// flags, or arrays of strings for options that can be repeated.
+fn load_config(file: &str) -> Result<Args, String> {
+    let text = std::fs::read_to_string(file).map_err(|error| format!("{}: {}", file, error))?;
+    let json: serde_json::Value =
+        serde_json::from_str(&text).map_err(|error| format!("{}: {}", file, error))?;
+    let object = match json {
+        serde_json::Value::Object(object) => object,
+        _ => return Err(format!("{}: expected an object", file)),
     };
 
-    let source = match std::fs::read(file) {
-        Ok(source) => source,
-        Err(error) => err(&error.to_string()),
+    let mut config = Args::default();
+    for (key, value) in object {
+        let values = match value {
+            serde_json::Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let value = match value {
+                serde_json::Value::String(value) => value,
+                serde_json::Value::Bool(value) => value.to_string(),
+                value => return Err(format!("{}: invalid value for {}: {}", file, key, value)),
+            };
+            config
+                .set(&key, &value)
+                .map_err(|message| format!("{}: {}", file, message))?;
+        }
+    }
+    Ok(config)
+}
+
+/// How to walk directories, from `--include`, `--exclude` and `--ext`.
+fn walk(args: &Args) -> Walk {
+    let mut walk = Walk::new();
+    for glob in &args.include {
+        walk = walk.include(Glob::new(glob));
+    }
+    for glob in &args.exclude {
+        walk = walk.exclude(Glob::new(glob));
+    }
+    if !args.extensions.is_empty() {
+        walk = walk.extensions(&args.extensions);
+    }
+    walk
+}
+
+/// Read a rules file: `{ "rules": [...] }`, where each rule has a `policy` of `deny` or `allow`,
+/// an optional `name`, and conditions: `from` and `to` file globs, a `package` name pattern, a
+/// `kind` or list of kinds, and `deep`.
+fn load_rules(file: &str) -> Result<Rules, String> {
+    let text = std::fs::read_to_string(file).map_err(|error| format!("{}: {}", file, error))?;
+    let json: serde_json::Value =
+        serde_json::from_str(&text).map_err(|error| format!("{}: {}", file, error))?;
+    let list = match json.get("rules") {
+        Some(serde_json::Value::Array(list)) => list,
+        _ => return Err(format!("{}: expected an object with a rules array", file)),
+    };
+
+    let mut rules = Rules::new();
+    for (index, object) in list.iter().enumerate() {
+        let invalid = |key: &str| format!("{}: invalid {} in rule {}", file, key, index + 1);
+        let string = |key: &str| match object.get(key) {
+            None => Ok(None),
+            Some(serde_json::Value::String(value)) => Ok(Some(value.as_str())),
+            Some(_) => Err(invalid(key)),
+        };
+        let name = match string("name")? {
+            Some(name) => name.to_string(),
+            None => format!("rule {}", index + 1),
+        };
+        let mut rule = match string("policy")? {
+            Some("deny") => Rule::deny(&name),
+            Some("allow") => Rule::allow(&name),
+            _ => return Err(invalid("policy")),
+        };
+        if let Some(glob) = string("from")? {
+            rule = rule.from(Glob::new(glob));
+        }
+        if let Some(glob) = string("to")? {
+            rule = rule.to(Glob::new(glob));
+        }
+        if let Some(pattern) = string("package")? {
+            rule = rule.package(pattern);
+        }
+        let kinds = match object.get("kind") {
+            None => vec![],
+            Some(serde_json::Value::Array(kinds)) => kinds.iter().collect(),
+            Some(kind) => vec![kind],
+        };
+        for kind in kinds {
+            match kind.as_str().and_then(EdgeKind::from_name) {
+                Some(kind) => rule = rule.kind(kind),
+                None => return Err(invalid("kind")),
+            }
+        }
+        match object.get("deep") {
+            None => (),
+            Some(serde_json::Value::Bool(deep)) => rule = rule.deep(*deep),
+            Some(_) => return Err(invalid("deep")),
+        }
+        rules = rules.rule(rule);
+    }
+    Ok(rules)
+}
+
+/// Expand the path arguments into the list of files to scan.
+fn collect_files(args: &Args) -> Result<Vec<PathBuf>, String> {
+    let walk = walk(args);
+    let mut files = vec![];
+    for path in &args.paths {
+        if path == STDIN {
+            files.push(PathBuf::from(STDIN));
+        } else if Path::new(path).exists() {
+            files.extend(
+                walk.files(Path::new(path))
+                    .map_err(|error| format!("{}: {}", path, error))?,
+            );
+        } else if Glob::is_glob(path) {
+            let (base, pattern) = Glob::split_base(path);
+            // Anchor the pattern to the base directory, so `*.js` does not match in subdirectories.
+            let glob = Glob::new(&format!("./{}", pattern));
+            let base = Path::new(base);
+            let matches = walk
+                .clone()
+                .extensions::<&str>(&[])
+                .files(base)
+                .map_err(|error| format!("{}: {}", path, error))?;
+            files.extend(matches.into_iter().filter(|file| {
+                let relative = file.strip_prefix(base).unwrap_or(file);
+                glob.is_match(&relative.to_string_lossy())
+            }));
+        } else {
+            return Err(format!("{}: no such file or directory", path));
+        }
+    }
+    let mut seen = HashSet::new();
+    files.retain(|file| seen.insert(file.clone()));
+    Ok(files)
+}
+
+enum Failure {
+    Read(std::io::Error),
+    Decode(Error),
+    Syntax { error: Error, source: String },
+}
+
+impl Failure {
+    fn to_text(&self, file: &Path) -> String {
+        match self {
+            Failure::Read(error) => format!("{}: {}", file.display(), error),
+            Failure::Decode(error) => format!(
+                "{}:{}:{}: {}",
+                file.display(),
+                error.line,
+                error.column,
+                error.message
+            ),
+            Failure::Syntax { error, source } => format!(
+                "{}:{}:{}: {}\n{}",
+                file.display(),
+                error.line,
+                error.column,
+                error.message,
+                error.code_frame(source)
+            ),
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum RecordError<'a> {
+    Source(&'a Error),
+    Read { kind: &'static str, message: String },
+}
+
+/// The JSON output for one file. Either the fields of `Found` or an `error` are present.
+#[derive(Serialize)]
+struct Record<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    version: Option<u32>,
+    file: String,
+    #[serde(flatten)]
+    found: Option<&'a Found>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<RecordError<'a>>,
+}
+
+impl<'a> Record<'a> {
+    fn new(file: &Path, result: &'a Result<Found, Failure>) -> Self {
+        let (found, error) = match result {
+            Ok(found) => (Some(found), None),
+            Err(Failure::Decode(error)) | Err(Failure::Syntax { error, .. }) => {
+                (None, Some(RecordError::Source(error)))
+            }
+            Err(Failure::Read(error)) => (
+                None,
+                Some(RecordError::Read {
+                    kind: "read",
+                    message: error.to_string(),
+                }),
+            ),
+        };
+        Self {
+            version: None,
+            file: file.display().to_string(),
+            found,
+            error,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Output<'a> {
+    version: u32,
+    files: Vec<Record<'a>>,
+}
+
+fn scan(file: &Path, options: &Options) -> Result<Found, Failure> {
+    let source = if file == Path::new(STDIN) {
+        let mut source = vec![];
+        std::io::stdin()
+            .read_to_end(&mut source)
+            .map_err(Failure::Read)?;
+        source
+    } else {
+        std::fs::read(file).map_err(Failure::Read)?
     };
+    let source = decode(&source, options).map_err(Failure::Decode)?;
+    find(&source, options).map_err(|error| Failure::Syntax {
+        error,
+        source: source.into_owned(),
+    })
+}
+
+/// Walk the dependency graph from the entry `files`. Modules that could not be read or parsed and
+/// requires that could not be resolved are reported on stderr, and the command's own result
+/// decides the exit status. Commands whose output would be wrong for an incomplete graph, not
+/// just incomplete, pass `complete` to exit with 1 instead of printing anything.
+fn build_graph(args: &Args, files: &[PathBuf], sources: bool, complete: bool) -> Graph {
+    if files.iter().any(|file| file == Path::new(STDIN)) {
+        err("the dependency graph cannot be read from stdin");
+    }
+    let entries = files
+        .iter()
+        .map(|file| cwd().join(file))
+        .collect::<Vec<_>>();
 
-    let source = std::str::from_utf8(&source).unwrap();
-    let result = match detective(source, &Default::default()) {
-        Ok(result) => result,
-        Err(error) => err(&error.to_string()),
+    let mut resolver = Resolver::new();
+    if args.browser == Some(true) {
+        resolver = resolver
+            .browser(true)
+            .conditions(&["browser", "require", "default"]);
+    }
+    let graph = GraphBuilder::with_resolver(resolver)
+        .options(args.options(Path::new("")))
+        .sources(sources)
+        .build(&entries);
+
+    let mut ok = true;
+    for module in &graph.modules {
+        if let Some(error) = &module.error {
+            eprintln!(
+                "{}:{}:{}: {}",
+                module.file.display(),
+                error.line,
+                error.column,
+                error.message
+            );
+            ok = false;
+        }
+        for edge in &module.edges {
+            if let Target::Unresolved(error) = &edge.target {
+                eprintln!(
+                    "{}:{}:{}: {}",
+                    module.file.display(),
+                    edge.line,
+                    edge.column,
+                    error
+                );
+                ok = false;
+            }
+        }
+    }
+    if complete && !ok {
+        err("the dependency graph is incomplete");
+    }
+    graph
+}
+
+fn cwd() -> PathBuf {
+    std::env::current_dir().unwrap_or_else(|error| err(&error.to_string()))
+}
+
+/// How to show a file in reports: relative to the working directory if it is inside it.
+fn display(file: &Path) -> String {
+    let cwd = cwd();
+    file.strip_prefix(&cwd)
+        .unwrap_or(file)
+        .display()
+        .to_string()
+}
+
+#[derive(Serialize)]
+struct EdgeRecord {
+    file: String,
+    line: usize,
+    column: usize,
+    specifier: String,
+    kind: EdgeKind,
+    target: String,
+}
+
+impl EdgeRecord {
+    fn new(graph: &Graph, module: usize, edge: usize) -> Self {
+        let module = &graph.modules[module];
+        let edge = &module.edges[edge];
+        let target = match &edge.target {
+            Target::Module(target) => display(&graph.modules[*target].file),
+            Target::Builtin(name) => name.clone(),
+            Target::Empty => "(empty)".to_string(),
+            Target::Unresolved(_) => "(unresolved)".to_string(),
+        };
+        Self {
+            file: display(&module.file),
+            line: edge.line,
+            column: edge.column,
+            specifier: edge.specifier.clone(),
+            kind: edge.kind,
+            target,
+        }
+    }
+
+    fn to_text(&self) -> String {
+        format!(
+            "{}:{}:{}: {} '{}' -> {}",
+            self.file,
+            self.line,
+            self.column,
+            self.kind.name(),
+            self.specifier,
+            self.target
+        )
+    }
+}
+
+#[derive(Serialize)]
+struct CycleRecord {
+    hazard: Hazard,
+    modules: Vec<String>,
+    edges: Vec<EdgeRecord>,
+}
+
+/// Report the cycles in the graph. Returns whether there were no eager ones.
+fn print_cycles(graph: &Graph, format: Format) -> bool {
+    let cycles = graph.cycles();
+    let records = cycles
+        .iter()
+        .map(|cycle| CycleRecord {
+            hazard: cycle.hazard,
+            modules: cycle
+                .modules
+                .iter()
+                .map(|&module| display(&graph.modules[module].file))
+                .collect(),
+            edges: cycle
+                .edges
+                .iter()
+                .map(|&(module, edge)| EdgeRecord::new(graph, module, edge))
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    match format {
+        Format::Json => {
+            let output = serde_json::json!({ "version": SCHEMA_VERSION, "cycles": records });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+        _ => {
+            for record in &records {
+                let hazard = match record.hazard {
+                    Hazard::Eager => "eager",
+                    Hazard::Deferred => "deferred",
+                };
+                println!("{} cycle: {}", hazard, record.modules.join(", "));
+                for edge in &record.edges {
+                    println!("  {}", edge.to_text());
+                }
+            }
+        }
+    }
+    cycles.iter().all(|cycle| cycle.hazard != Hazard::Eager)
+}
+
+/// Check the package.json in the directory given on the command line against what its files
+/// require. Returns whether everything matched.
+fn print_dependencies(args: &Args, files: &[PathBuf]) -> bool {
+    let dir = Path::new(&args.paths[0]);
+    let manifest = dir.join("package.json");
+    let text = std::fs::read_to_string(&manifest)
+        .unwrap_or_else(|error| err(&format!("{}: {}", manifest.display(), error)));
+    let mut check = DependencyCheck::new(&text)
+        .unwrap_or_else(|error| err(&format!("{}: {}", manifest.display(), error)));
+    for glob in &args.ignore {
+        check = check.ignore(Glob::new(glob));
+    }
+
+    let mut ok = true;
+    for file in files {
+        match scan(file, &args.options(file)) {
+            Ok(found) => check.add(file, &found),
+            Err(failure) => {
+                eprintln!("{}", failure.to_text(file));
+                ok = false;
+            }
+        }
+    }
+
+    let unused = check.unused();
+    let missing = check.missing();
+    match args.format.unwrap_or_default() {
+        Format::Json => {
+            let output = serde_json::json!({
+                "version": SCHEMA_VERSION,
+                "unused": unused,
+                "missing": missing,
+            });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+        _ => {
+            if !unused.is_empty() {
+                println!("unused dependencies:");
+                for unused in &unused {
+                    println!("  {} ({})", unused.name, unused.field);
+                }
+            }
+            if !missing.is_empty() {
+                println!("missing dependencies:");
+                for missing in &missing {
+                    println!("  {}", missing.name);
+                    for usage in &missing.usages {
+                        println!(
+                            "    {}:{}:{}: '{}'",
+                            usage.file.display(),
+                            usage.line,
+                            usage.column,
+                            usage.specifier
+                        );
+                    }
+                }
+            }
+        }
+    }
+    ok && unused.is_empty() && missing.is_empty()
+}
+
+#[derive(Serialize)]
+struct NewerBuiltinRecord<'a> {
+    file: String,
+    #[serde(flatten)]
+    builtin: &'a NewerBuiltin,
+}
+
+/// Report builtins that need a newer Node than the configured minimum. Returns whether there
+/// were none.
+fn print_builtins(args: &Args, files: &[PathBuf]) -> bool {
+    let minimum = args.node.or_else(|| {
+        let json = std::fs::read_to_string("package.json").ok()?;
+        let json: serde_json::Value = serde_json::from_str(&json).ok()?;
+        NodeVersion::from_engines(json.get("engines")?.get("node")?.as_str()?)
+    });
+    let minimum = minimum.unwrap_or_else(|| {
+        err("missing --node, and there is no engines.node in ./package.json to use instead")
+    });
+
+    let mut ok = true;
+    let mut newer = vec![];
+    for file in files {
+        match scan(file, &args.options(&args.name(file))) {
+            Ok(found) => newer.push((args.name(file), newer_builtins(&found, minimum))),
+            Err(failure) => {
+                eprintln!("{}", failure.to_text(&args.name(file)));
+                ok = false;
+            }
+        }
+    }
+
+    match args.format.unwrap_or_default() {
+        Format::Json => {
+            let records = newer
+                .iter()
+                .flat_map(|(file, newer)| {
+                    newer.iter().map(move |builtin| NewerBuiltinRecord {
+                        file: file.display().to_string(),
+                        builtin,
+                    })
+                })
+                .collect::<Vec<_>>();
+            let output = serde_json::json!({
+                "version": SCHEMA_VERSION,
+                "node": minimum,
+                "builtins": records,
+            });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+        _ => {
+            for (file, newer) in &newer {
+                for builtin in newer {
+                    println!(
+                        "{}:{}:{}: '{}' needs Node {}, but the minimum is {}",
+                        file.display(),
+                        builtin.line,
+                        builtin.column,
+                        builtin.specifier,
+                        builtin.needs,
+                        minimum
+                    );
+                }
+            }
+        }
+    }
+    ok && newer.iter().all(|(_, newer)| newer.is_empty())
+}
+
+#[derive(Serialize)]
+struct BrowserBuiltinRecord<'a> {
+    file: String,
+    #[serde(flatten)]
+    builtin: &'a BrowserBuiltin,
+}
+
+/// Report the browser support of every required core module, grouped by module. Returns whether
+/// all of them can be bundled.
+fn print_polyfills(args: &Args, files: &[PathBuf]) -> bool {
+    let mut ok = true;
+    let mut builtins = vec![];
+    for file in files {
+        match scan(file, &args.options(&args.name(file))) {
+            Ok(found) => builtins.extend(
+                browser_builtins(&found)
+                    .into_iter()
+                    .map(|builtin| (args.name(file), builtin)),
+            ),
+            Err(failure) => {
+                eprintln!("{}", failure.to_text(&args.name(file)));
+                ok = false;
+            }
+        }
+    }
+    builtins.sort_by_key(|(_, builtin)| builtin.name);
+
+    match args.format.unwrap_or_default() {
+        Format::Json => {
+            let records = builtins
+                .iter()
+                .map(|(file, builtin)| BrowserBuiltinRecord {
+                    file: file.display().to_string(),
+                    builtin,
+                })
+                .collect::<Vec<_>>();
+            let output = serde_json::json!({ "version": SCHEMA_VERSION, "builtins": records });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+        _ => {
+            let mut previous = None;
+            for (file, builtin) in &builtins {
+                if previous != Some(builtin.name) {
+                    let support = match builtin.support {
+                        BrowserSupport::Shim { package } => format!("shimmed by {}", package),
+                        BrowserSupport::Polyfill { package } => {
+                            format!("needs the {} polyfill", package)
+                        }
+                        BrowserSupport::Empty => "replaced by an empty object".to_string(),
+                        BrowserSupport::Unsupported => "not available in browsers".to_string(),
+                    };
+                    println!("{}: {}", builtin.name, support);
+                    previous = Some(builtin.name);
+                }
+                println!("  {}:{}:{}", file.display(), builtin.line, builtin.column);
+            }
+        }
+    }
+    ok && builtins
+        .iter()
+        .all(|(_, builtin)| builtin.support != BrowserSupport::Unsupported)
+}
+
+#[derive(Serialize)]
+struct ChainRecord {
+    entry: String,
+    hops: Vec<EdgeRecord>,
+}
+
+#[derive(Serialize)]
+struct ViolationRecord {
+    rule: String,
+    #[serde(flatten)]
+    edge: EdgeRecord,
+}
+
+/// Report the dependencies that break the rules. Returns whether there were none.
+fn print_violations(graph: &Graph, rules: &Rules, format: Format) -> bool {
+    let records = graph
+        .check(rules)
+        .into_iter()
+        .map(|violation| ViolationRecord {
+            edge: EdgeRecord::new(graph, violation.module, violation.edge),
+            rule: violation.rule,
+        })
+        .collect::<Vec<_>>();
+
+    match format {
+        Format::Json => {
+            let output = serde_json::json!({ "version": SCHEMA_VERSION, "violations": records });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+        _ => {
+            for record in &records {
+                println!("{} breaks {}", record.edge.to_text(), record.rule);
+            }
+        }
+    }
+    records.is_empty()
+}
+
+#[derive(Serialize)]
+struct MetricsRecord {
+    file: String,
+    #[serde(flatten)]
+    metrics: Metrics,
+}
+
+/// Print the metrics of every module as a table or JSON, sorted by `sort`.
+fn print_metrics(graph: &Graph, sort: SortKey, format: Format) {
+    let mut records = graph
+        .metrics()
+        .into_iter()
+        .zip(&graph.modules)
+        .map(|(metrics, module)| MetricsRecord {
+            file: display(&module.file),
+            metrics,
+        })
+        .collect::<Vec<_>>();
+    // Numbers sort largest first, and ties by file name.
+    records.sort_by(|a, b| a.file.cmp(&b.file));
+    records.sort_by(|a, b| {
+        let (a, b) = (&a.metrics, &b.metrics);
+        match sort {
+            SortKey::File => std::cmp::Ordering::Equal,
+            SortKey::FanIn => b.fan_in.cmp(&a.fan_in),
+            SortKey::FanOut => b.fan_out.cmp(&a.fan_out),
+            SortKey::Transitive => b.transitive.cmp(&a.transitive),
+            SortKey::Size => b.transitive_size.cmp(&a.transitive_size),
+            SortKey::Depth => b.depth.cmp(&a.depth),
+            SortKey::Instability => b.instability.total_cmp(&a.instability),
+        }
+    });
+
+    match format {
+        Format::Json => {
+            let output = serde_json::json!({ "version": SCHEMA_VERSION, "modules": records });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+        _ => {
+            println!(
+                "{:>6} {:>7} {:>10} {:>10} {:>5} {:>11}  file",
+                "fan-in", "fan-out", "transitive", "size", "depth", "instability"
+            );
+            for record in &records {
+                let metrics = &record.metrics;
+                let depth = match metrics.depth {
+                    Some(depth) => depth.to_string(),
+                    None => "-".to_string(),
+                };
+                println!(
+                    "{:>6} {:>7} {:>10} {:>10} {:>5} {:>11.2}  {}",
+                    metrics.fan_in,
+                    metrics.fan_out,
+                    metrics.transitive,
+                    metrics.transitive_size,
+                    depth,
+                    metrics.instability,
+                    record.file
+                );
+            }
+        }
+    }
+}
+
+/// The `files` field of ./package.json, if there is one.
+fn published_files() -> Option<PublishedFiles> {
+    let text = std::fs::read_to_string("package.json").ok()?;
+    let json: serde_json::Value = serde_json::from_str(&text)
+        .unwrap_or_else(|error| err(&format!("package.json: {}", error)));
+    let files = json.get("files")?.as_array()?;
+    let entries = files
+        .iter()
+        .filter_map(|entry| entry.as_str())
+        .collect::<Vec<_>>();
+    Some(PublishedFiles::new(&entries))
+}
+
+/// List the files that no entry reaches. Returns whether there were none.
+fn print_unreachable(args: &Args, graph: &Graph) -> bool {
+    let root = cwd();
+    let mut candidates = walk(args)
+        .files(&root)
+        .unwrap_or_else(|error| err(&format!("{}: {}", root.display(), error)));
+    // Files that are not published cannot be required by anyone else either.
+    if let Some(published) = published_files() {
+        candidates.retain(|file| published.is_match(file.strip_prefix(&root).unwrap_or(file)));
+    }
+    let files = graph
+        .unreachable(&candidates)
+        .iter()
+        .map(|file| display(file))
+        .collect::<Vec<_>>();
+
+    match args.format.unwrap_or_default() {
+        Format::Json => {
+            let output = serde_json::json!({ "version": SCHEMA_VERSION, "unreachable": files });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+        _ => {
+            for file in &files {
+                println!("{}", file);
+            }
+        }
+    }
+    files.is_empty()
+}
+
+/// Print the shortest chains from the entries to `module`. Returns whether there were any.
+fn print_why(graph: &Graph, module: &str, format: Format) -> bool {
+    let targets = match graph.find(cwd().join(module)) {
+        Some(index) => vec![index],
+        None => graph.find_package(module),
     };
+    // Empty chains come first, one for each entry that is a target, in the order of the entries.
+    let mut targeted_entries = graph.entries().filter(|entry| targets.contains(entry));
+    let records = graph
+        .why(&targets)
+        .into_iter()
+        .map(|chain| ChainRecord {
+            entry: match chain.first() {
+                Some(&(entry, _)) => display(&graph.modules[entry].file),
+                None => display(&graph.modules[targeted_entries.next().unwrap()].file),
+            },
+            hops: chain
+                .iter()
+                .map(|&(module, edge)| EdgeRecord::new(graph, module, edge))
+                .collect(),
+        })
+        .collect::<Vec<_>>();
+
+    match format {
+        Format::Json => {
+            let output = serde_json::json!({ "version": SCHEMA_VERSION, "chains": records });
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+        _ if records.is_empty() => eprintln!("{} is not reachable from the entry files", module),
+        _ => {
+            for record in &records {
+                println!("{}", record.entry);
+                if record.hops.is_empty() {
+                    println!("  (entry file)");
+                }
+                for hop in &record.hops {
+                    println!("  {}", hop.to_text());
+                }
+            }
+        }
+    }
+    !records.is_empty()
+}
+
+fn main() {
+    let mut args = parse_args(std::env::args().skip(1));
+    if let Some(config) = &args.config {
+        let config = load_config(config).unwrap_or_else(|message| err(&message));
+        args = args.or(config);
+    }
+
+    let files = collect_files(&args).unwrap_or_else(|message| err(&message));
+    match &args.command {
+        Command::Scan => (),
+        Command::Cycles => {
+            let graph = build_graph(&args, &files, false, false);
+            let ok = print_cycles(&graph, args.format.unwrap_or_default());
+            std::process::exit(if ok { 0 } else { 1 });
+        }
+        Command::Dependencies => {
+            let ok = print_dependencies(&args, &files);
+            std::process::exit(if ok { 0 } else { 1 });
+        }
+        Command::Builtins => {
+            let ok = print_builtins(&args, &files);
+            std::process::exit(if ok { 0 } else { 1 });
+        }
+        Command::Polyfills => {
+            let ok = print_polyfills(&args, &files);
+            std::process::exit(if ok { 0 } else { 1 });
+        }
+        Command::Unreachable => {
+            let graph = build_graph(&args, &files, false, true);
+            let ok = print_unreachable(&args, &graph);
+            std::process::exit(if ok { 0 } else { 1 });
+        }
+        Command::Check => {
+            let file = match &args.rules {
+                Some(file) => file,
+                None => err(&format!("missing --rules for check\n\n{}", USAGE)),
+            };
+            let rules = load_rules(file).unwrap_or_else(|message| err(&message));
+            let graph = build_graph(&args, &files, false, false);
+            let ok = print_violations(&graph, &rules.root(cwd()), args.format.unwrap_or_default());
+            std::process::exit(if ok { 0 } else { 1 });
+        }
+        Command::Metrics => {
+            let graph = build_graph(&args, &files, false, false);
+            print_metrics(
+                &graph,
+                args.sort.unwrap_or_default(),
+                args.format.unwrap_or_default(),
+            );
+            std::process::exit(0);
+        }
+        Command::Why(module) => {
+            let graph = build_graph(&args, &files, false, false);
+            let found = print_why(&graph, module, args.format.unwrap_or_default());
+            std::process::exit(if found { 0 } else { 1 });
+        }
+    }
+    match args.format {
+        Some(Format::Deps) => {
+            let graph = build_graph(&args, &files, true, false);
+            println!("{}", serde_json::to_string_pretty(&graph.rows()).unwrap());
+            return;
+        }
+        Some(Format::Export(format)) => {
+            let graph = build_graph(&args, &files, false, false);
+            let options = ExportOptions::new()
+                .root(cwd())
+                .collapse_packages(args.collapse_packages == Some(true))
+                .color_edges(args.color_edges == Some(true));
+            print!("{}", graph.export(format, &options));
+            return;
+        }
+        _ => (),
+    }
+    let results = files
+        .iter()
+        .map(|file| scan(file, &args.options(&args.name(file))))
+        .collect::<Vec<_>>();
+    let files = files.iter().map(|file| args.name(file)).collect::<Vec<_>>();
+    let failed = results.iter().any(|result| result.is_err());
+
+    match args.format.unwrap_or_default() {
+        Format::Text => {
+            // A single file prints just the names, like before. Anything else is grouped per file.
+            let single_file = args.paths[0] == STDIN || Path::new(&args.paths[0]).is_file();
+            let grouped = files.len() != 1 || !single_file;
+            for (file, result) in files.iter().zip(&results) {
+                match result {
+                    Ok(found) => {
+                        if grouped {
+                            println!("{}", file.display());
+                        }
+                        for name in &found.strings {
+                            if grouped {
+                                println!("  {}", name);
+                            } else {
+                                println!("{}", name);
+                            }
+                        }
+                        // Syntax errors skipped by --tolerant.
+                        for error in &found.diagnostics {
+                            eprintln!(
+                                "{}:{}:{}: {}",
+                                file.display(),
+                                error.line,
+                                error.column,
+                                error.message
+                            );
+                        }
+                    }
+                    Err(failure) => eprintln!("{}", failure.to_text(file)),
+                }
+            }
+        }
+        Format::Json => {
+            let output = Output {
+                version: SCHEMA_VERSION,
+                files: files
+                    .iter()
+                    .zip(&results)
+                    .map(|(file, result)| Record::new(file, result))
+                    .collect(),
+            };
+            println!("{}", serde_json::to_string_pretty(&output).unwrap());
+        }
+        Format::Ndjson => {
+            for (file, result) in files.iter().zip(&results) {
+                let record = Record {
+                    version: Some(SCHEMA_VERSION),
+                    ..Record::new(file, result)
+                };
+                println!("{}", serde_json::to_string(&record).unwrap());
+            }
+        }
+        Format::Deps | Format::Export(_) => unreachable!(),
+    }
 
-    for name in result {
-        println!("{}", name);
+    if failed {
+        std::process::exit(1);
     }
 }
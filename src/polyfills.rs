@@ -0,0 +1,170 @@
+//! How well Node core modules work in browsers.
+
+use crate::{builtin_module, Found};
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+
+/// What happens to a core module when it is bundled for browsers.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize),
+    serde(tag = "status", rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BrowserSupport {
+    /// Browserify substitutes this shim package automatically.
+    Shim { package: &'static str },
+    /// Works with this polyfill package, but bundlers do not add it on their own.
+    Polyfill { package: &'static str },
+    /// Browserify substitutes an empty object, so requiring it works but using it does not.
+    Empty,
+    /// There is no browser equivalent.
+    Unsupported,
+}
+
+/// The shims browserify uses, from its `lib/builtins.js`.
+const SHIMS: &[(&str, &str)] = &[
+    ("assert", "assert"),
+    ("buffer", "buffer"),
+    ("console", "console-browserify"),
+    ("constants", "constants-browserify"),
+    ("crypto", "crypto-browserify"),
+    ("domain", "domain-browser"),
+    ("events", "events"),
+    ("http", "stream-http"),
+    ("https", "https-browserify"),
+    ("os", "os-browserify"),
+    ("path", "path-browserify"),
+    ("process", "process"),
+    ("punycode", "punycode"),
+    ("querystring", "querystring-es3"),
+    ("stream", "stream-browserify"),
+    ("string_decoder", "string_decoder"),
+    ("sys", "util"),
+    ("timers", "timers-browserify"),
+    ("tty", "tty-browserify"),
+    ("url", "url"),
+    ("util", "util"),
+    ("vm", "vm-browserify"),
+    ("zlib", "browserify-zlib"),
+];
+
+const POLYFILLS: &[(&str, &str)] = &[
+    ("diagnostics_channel", "diagnostics_channel"),
+    ("path/posix", "path-browserify"),
+    ("stream/web", "web-streams-polyfill"),
+];
+
+/// The modules browserify replaces by an empty object.
+const EMPTY: &[&str] = &[
+    "child_process",
+    "cluster",
+    "dgram",
+    "dns",
+    "fs",
+    "http2",
+    "inspector",
+    "module",
+    "net",
+    "perf_hooks",
+    "readline",
+    "repl",
+    "tls",
+];
+
+/// How the core module `specifier` can be used in browsers, or `None` if it is not a core module.
+pub fn browser_support(specifier: &str) -> Option<BrowserSupport> {
+    let name = builtin_module(specifier)?.name;
+    let lookup = |table: &[(&str, &'static str)]| {
+        table
+            .iter()
+            .find(|(builtin, _)| *builtin == name)
+            .map(|(_, package)| *package)
+    };
+    let support = if let Some(package) = lookup(SHIMS) {
+        BrowserSupport::Shim { package }
+    } else if let Some(package) = lookup(POLYFILLS) {
+        BrowserSupport::Polyfill { package }
+    } else if EMPTY.contains(&name) {
+        BrowserSupport::Empty
+    } else {
+        BrowserSupport::Unsupported
+    };
+    Some(support)
+}
+
+/// A require of a core module, with how it can be used in browsers.
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BrowserBuiltin {
+    pub specifier: String,
+    /// The module name, without the `node:` prefix.
+    pub name: &'static str,
+    /// 1-based line number.
+    pub line: usize,
+    /// 1-based column, counted in bytes.
+    pub column: usize,
+    pub support: BrowserSupport,
+}
+
+/// Find the requires of core modules in `found`, with their browser support.
+pub fn browser_builtins(found: &Found) -> Vec<BrowserBuiltin> {
+    found
+        .requires
+        .iter()
+        .filter_map(|require| {
+            Some(BrowserBuiltin {
+                specifier: require.specifier.clone(),
+                name: builtin_module(&require.specifier)?.name,
+                line: require.line,
+                column: require.column,
+                support: browser_support(&require.specifier)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find, Options};
+
+    #[test]
+    fn support() {
+        assert_eq!(
+            browser_support("crypto"),
+            Some(BrowserSupport::Shim {
+                package: "crypto-browserify"
+            })
+        );
+        assert_eq!(
+            browser_support("node:path/posix"),
+            Some(BrowserSupport::Polyfill {
+                package: "path-browserify"
+            })
+        );
+        assert_eq!(browser_support("fs"), Some(BrowserSupport::Empty));
+        assert_eq!(
+            browser_support("worker_threads"),
+            Some(BrowserSupport::Unsupported)
+        );
+        assert_eq!(browser_support("lodash"), None);
+    }
+
+    #[test]
+    fn found() {
+        let source = "require('node:buffer');\nrequire('./fs');\nrequire('fs');";
+        let found = find(source, &Options::new()).unwrap();
+        let builtins = browser_builtins(&found)
+            .into_iter()
+            .map(|builtin| (builtin.name, builtin.line, builtin.support))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            builtins,
+            vec![
+                ("buffer", 1, BrowserSupport::Shim { package: "buffer" }),
+                ("fs", 3, BrowserSupport::Empty),
+            ]
+        );
+    }
+}
@@ -0,0 +1,158 @@
+//! The package.json `browser` field, as specified in
+//! https://github.com/defunctzombie/package-browser-field-spec.
+
+use super::{normalize, FileSystem, Package, Resolution, ResolveError, Resolver};
+use serde_json::{Map, Value};
+use std::path::Path;
+
+fn browser_map(package: &Package) -> Option<&Map<String, Value>> {
+    package
+        .json
+        .get("browser")
+        .and_then(|browser| browser.as_object())
+}
+
+impl<F: FileSystem> Resolver<F> {
+    pub(super) fn resolve_browser(
+        &self,
+        specifier: &str,
+        dir: &Path,
+    ) -> Result<Resolution, ResolveError> {
+        // Module names can be replaced by the package that requires them.
+        if let Some(package) = self.package_scope(dir)? {
+            if let Some(replacement) = browser_map(&package).and_then(|map| map.get(specifier)) {
+                return self.replace(&package, replacement);
+            }
+        }
+
+        match self.resolve_node(specifier, dir)? {
+            Resolution::File(file) => self.replace_file(Resolution::File(file)),
+            resolution => Ok(resolution),
+        }
+    }
+
+    /// Files can be replaced by the package that contains them.
+    fn replace_file(&self, resolution: Resolution) -> Result<Resolution, ResolveError> {
+        let file = match &resolution {
+            Resolution::File(file) => file,
+            _ => return Ok(resolution),
+        };
+        let package = match file
+            .parent()
+            .map(|dir| self.package_scope(dir))
+            .transpose()?
+        {
+            Some(Some(package)) => package,
+            _ => return Ok(resolution),
+        };
+        let map = match browser_map(&package) {
+            Some(map) => map,
+            None => return Ok(resolution),
+        };
+
+        for (key, replacement) in map {
+            if !key.starts_with("./") && !key.starts_with("../") {
+                continue;
+            }
+            // Keys may leave out the extension.
+            let path = normalize(&package.dir.join(key));
+            if &path == file || self.load_file(&path).as_ref() == Some(file) {
+                return self.replace(&package, replacement);
+            }
+        }
+        Ok(resolution)
+    }
+
+    fn replace(&self, package: &Package, replacement: &Value) -> Result<Resolution, ResolveError> {
+        match replacement {
+            Value::Bool(false) => Ok(Resolution::Empty),
+            Value::String(replacement) => self.resolve_node(replacement, &package.dir),
+            replacement => Err(ResolveError::InvalidPackageJson {
+                path: package.dir.join("package.json"),
+                message: format!("invalid \"browser\" replacement {}", replacement),
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemoryFileSystem, Resolution, ResolveError, Resolver};
+    use std::path::{Path, PathBuf};
+
+    fn fixture() -> MemoryFileSystem {
+        MemoryFileSystem::new()
+            .file(
+                "/app/package.json",
+                r#"{
+                    "browser": {
+                        "fs": false,
+                        "http": "stream-http",
+                        "./lib/server.js": "./lib/client.js",
+                        "./lib/debug": false
+                    }
+                }"#,
+            )
+            .file("/app/index.js", "")
+            .file("/app/lib/server.js", "")
+            .file("/app/lib/client.js", "")
+            .file("/app/lib/debug.js", "")
+            .file(
+                "/app/node_modules/a/package.json",
+                r#"{ "main": "node.js", "browser": "browser.js" }"#,
+            )
+            .file("/app/node_modules/a/node.js", "")
+            .file("/app/node_modules/a/browser.js", "")
+            .file(
+                "/app/node_modules/b/package.json",
+                r#"{ "browser": { "./index.js": "./shim.js", "crypto": false } }"#,
+            )
+            .file("/app/node_modules/b/index.js", "")
+            .file("/app/node_modules/b/shim.js", "")
+            .file("/app/node_modules/stream-http/index.js", "")
+    }
+
+    fn file(path: &str) -> Result<Resolution, ResolveError> {
+        Ok(Resolution::File(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn browser() {
+        let resolver = Resolver::with_fs(fixture()).browser(true);
+        let resolve = |specifier, dir| resolver.resolve(specifier, Path::new(dir));
+        assert_eq!(resolve("fs", "/app/lib"), Ok(Resolution::Empty));
+        assert_eq!(
+            resolve("http", "/app"),
+            file("/app/node_modules/stream-http/index.js")
+        );
+        assert_eq!(resolve("./server", "/app/lib"), file("/app/lib/client.js"));
+        assert_eq!(
+            resolve("../lib/debug.js", "/app/lib"),
+            Ok(Resolution::Empty)
+        );
+        assert_eq!(resolve("a", "/app"), file("/app/node_modules/a/browser.js"));
+        assert_eq!(resolve("b", "/app"), file("/app/node_modules/b/shim.js"));
+
+        // Module replacements only apply to the package that declares them.
+        assert_eq!(
+            resolve("crypto", "/app/node_modules/b"),
+            Ok(Resolution::Empty)
+        );
+        assert_eq!(
+            resolve("crypto", "/app"),
+            Ok(Resolution::Builtin("crypto".to_string()))
+        );
+    }
+
+    #[test]
+    fn node() {
+        let resolver = Resolver::with_fs(fixture());
+        let resolve = |specifier, dir| resolver.resolve(specifier, Path::new(dir));
+        assert_eq!(
+            resolve("fs", "/app"),
+            Ok(Resolution::Builtin("fs".to_string()))
+        );
+        assert_eq!(resolve("./lib/server", "/app"), file("/app/lib/server.js"));
+        assert_eq!(resolve("a", "/app"), file("/app/node_modules/a/node.js"));
+    }
+}
@@ -0,0 +1,308 @@
+//! The package.json `exports` and `imports` fields, following `PACKAGE_EXPORTS_RESOLVE` and
+//! `PACKAGE_IMPORTS_RESOLVE` from the Node documentation.
+
+use super::{FileSystem, Package, Resolution, ResolveError, Resolver};
+use serde_json::{Map, Value};
+
+impl<F: FileSystem> Resolver<F> {
+    /// Resolve `subpath`, like `.` or `./feature`, through the `exports` of `package`.
+    pub(super) fn resolve_exports(
+        &self,
+        package: &Package,
+        exports: &Value,
+        subpath: &str,
+        specifier: &str,
+    ) -> Result<Resolution, ResolveError> {
+        let subpath_keys = match exports {
+            Value::Object(map) => {
+                let dot_keys = map.keys().filter(|key| key.starts_with('.')).count();
+                if dot_keys != 0 && dot_keys != map.len() {
+                    return Err(ResolveError::InvalidPackageJson {
+                        path: package.dir.join("package.json"),
+                        message: "\"exports\" cannot mix subpaths and conditions".to_string(),
+                    });
+                }
+                dot_keys != 0
+            }
+            _ => false,
+        };
+
+        let resolved = match exports {
+            Value::Object(map) if subpath_keys => {
+                self.resolve_match(subpath, map, package, false, specifier)?
+            }
+            // Without subpath keys, `exports` is the export for ".".
+            exports if subpath == "." => {
+                self.resolve_target(package, exports, None, false, specifier)?
+            }
+            _ => None,
+        };
+        resolved.ok_or_else(|| ResolveError::NotExported {
+            specifier: specifier.to_string(),
+            package: package.dir.clone(),
+            subpath: subpath.to_string(),
+        })
+    }
+
+    /// Resolve a `#name` specifier through the `imports` of `package`.
+    pub(super) fn resolve_imports(
+        &self,
+        package: &Package,
+        specifier: &str,
+    ) -> Result<Resolution, ResolveError> {
+        let resolved = match package.json.get("imports") {
+            Some(Value::Object(map)) if specifier != "#" && !specifier.starts_with("#/") => {
+                self.resolve_match(specifier, map, package, true, specifier)?
+            }
+            _ => None,
+        };
+        resolved.ok_or_else(|| ResolveError::ImportNotDefined {
+            specifier: specifier.to_string(),
+            package: package.dir.clone(),
+        })
+    }
+
+    /// `PACKAGE_IMPORTS_EXPORTS_RESOLVE`: find the key in `map` that matches, exactly or as a
+    /// `*` pattern.
+    fn resolve_match(
+        &self,
+        key: &str,
+        map: &Map<String, Value>,
+        package: &Package,
+        imports: bool,
+        specifier: &str,
+    ) -> Result<Option<Resolution>, ResolveError> {
+        if let Some(target) = map.get(key).filter(|_| !key.contains('*')) {
+            return self.resolve_target(package, target, None, imports, specifier);
+        }
+
+        let mut patterns = map
+            .iter()
+            .filter_map(|(pattern, target)| {
+                let (base, trailer) = pattern.split_once('*')?;
+                (!trailer.contains('*')).then_some((pattern, base, trailer, target))
+            })
+            .collect::<Vec<_>>();
+        // The most specific pattern wins: longest base first, then longest pattern.
+        patterns.sort_by(|a, b| {
+            b.1.len()
+                .cmp(&a.1.len())
+                .then_with(|| b.0.len().cmp(&a.0.len()))
+        });
+
+        for (pattern, base, trailer, target) in patterns {
+            if key.len() > base.len()
+                && key.starts_with(base)
+                && key.ends_with(trailer)
+                && key.len() >= pattern.len()
+            {
+                let matched = &key[base.len()..key.len() - trailer.len()];
+                return self.resolve_target(package, target, Some(matched), imports, specifier);
+            }
+        }
+        Ok(None)
+    }
+
+    /// `PACKAGE_TARGET_RESOLVE`. `Ok(None)` means the target does not apply, because it is `null`
+    /// or none of its conditions match.
+    fn resolve_target(
+        &self,
+        package: &Package,
+        target: &Value,
+        pattern: Option<&str>,
+        imports: bool,
+        specifier: &str,
+    ) -> Result<Option<Resolution>, ResolveError> {
+        let invalid = |target: &str| ResolveError::InvalidPackageTarget {
+            specifier: specifier.to_string(),
+            package: package.dir.clone(),
+            target: target.to_string(),
+        };
+
+        match target {
+            Value::String(target) => {
+                let substituted = match pattern {
+                    Some(pattern) => target.replace('*', pattern),
+                    None => target.clone(),
+                };
+                if !target.starts_with("./") {
+                    // Imports may map to other packages.
+                    if imports && !target.starts_with("../") && !target.starts_with('/') {
+                        return self.resolve(&substituted, &package.dir).map(Some);
+                    }
+                    return Err(invalid(target));
+                }
+                if has_invalid_segment(&target[2..]) || pattern.is_some_and(has_invalid_segment) {
+                    return Err(invalid(target));
+                }
+                Ok(Some(Resolution::File(package.dir.join(&substituted[2..]))))
+            }
+            Value::Object(conditions) => {
+                for (condition, target) in conditions {
+                    if self.matches(condition) {
+                        if let Some(resolved) =
+                            self.resolve_target(package, target, pattern, imports, specifier)?
+                        {
+                            return Ok(Some(resolved));
+                        }
+                    }
+                }
+                Ok(None)
+            }
+            Value::Array(targets) => {
+                let mut last_error = None;
+                for target in targets {
+                    match self.resolve_target(package, target, pattern, imports, specifier) {
+                        Ok(Some(resolved)) => return Ok(Some(resolved)),
+                        Ok(None) => (),
+                        Err(error @ ResolveError::InvalidPackageTarget { .. }) => {
+                            last_error = Some(error)
+                        }
+                        Err(error) => return Err(error),
+                    }
+                }
+                last_error.map_or(Ok(None), Err)
+            }
+            Value::Null => Ok(None),
+            target => Err(invalid(&target.to_string())),
+        }
+    }
+}
+
+/// Targets may not escape the package or reach into `node_modules`.
+fn has_invalid_segment(path: &str) -> bool {
+    path.split(['/', '\\']).any(|segment| {
+        segment == "." || segment == ".." || segment.eq_ignore_ascii_case("node_modules")
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemoryFileSystem, Resolution, ResolveError, Resolver};
+    use std::path::{Path, PathBuf};
+
+    fn fixture() -> MemoryFileSystem {
+        MemoryFileSystem::new()
+            .file(
+                "/app/package.json",
+                r##"{
+                    "name": "app",
+                    "exports": { ".": "./index.js", "./feature": "./lib/feature.js" },
+                    "imports": {
+                        "#config": { "browser": "./config.browser.js", "default": "./config.js" },
+                        "#internal/*.js": "./lib/internal/*.js",
+                        "#dep": "dep",
+                        "#fs": { "node": "fs" }
+                    }
+                }"##,
+            )
+            .file("/app/index.js", "")
+            .file("/app/config.js", "")
+            .file("/app/config.browser.js", "")
+            .file("/app/lib/feature.js", "")
+            .file("/app/lib/internal/a.js", "")
+            .file(
+                "/app/node_modules/dep/package.json",
+                r#"{
+                    "main": "./legacy.js",
+                    "exports": {
+                        ".": { "import": "./esm/index.mjs", "require": "./cjs/index.js" },
+                        "./utils/*": "./cjs/utils/*.js",
+                        "./utils/private/*": null,
+                        "./package.json": "./package.json",
+                        "./missing": "./missing.js",
+                        "./escape": "./../app/index.js"
+                    }
+                }"#,
+            )
+            .file("/app/node_modules/dep/legacy.js", "")
+            .file("/app/node_modules/dep/esm/index.mjs", "")
+            .file("/app/node_modules/dep/cjs/index.js", "")
+            .file("/app/node_modules/dep/cjs/utils/a.js", "")
+            .file("/app/node_modules/dep/cjs/utils/private/b.js", "")
+            .file(
+                "/app/node_modules/sugar/package.json",
+                r#"{ "exports": { "node": ["./node.js"], "default": "./browser.js" } }"#,
+            )
+            .file("/app/node_modules/sugar/node.js", "")
+            .file("/app/node_modules/sugar/browser.js", "")
+            .file(
+                "/app/node_modules/mixed/package.json",
+                r#"{ "exports": { ".": "./index.js", "node": "./index.js" } }"#,
+            )
+    }
+
+    fn file(path: &str) -> Result<Resolution, ResolveError> {
+        Ok(Resolution::File(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn exports() {
+        let resolver = Resolver::with_fs(fixture());
+        let resolve = |specifier| resolver.resolve(specifier, Path::new("/app/lib"));
+        assert_eq!(resolve("dep"), file("/app/node_modules/dep/cjs/index.js"));
+        assert_eq!(
+            resolve("dep/utils/a"),
+            file("/app/node_modules/dep/cjs/utils/a.js")
+        );
+        assert_eq!(
+            resolve("dep/package.json"),
+            file("/app/node_modules/dep/package.json")
+        );
+        assert_eq!(resolve("sugar"), file("/app/node_modules/sugar/node.js"));
+        assert_eq!(
+            resolve("dep/legacy.js"),
+            Err(ResolveError::NotExported {
+                specifier: "dep/legacy.js".to_string(),
+                package: PathBuf::from("/app/node_modules/dep"),
+                subpath: "./legacy.js".to_string(),
+            })
+        );
+        assert!(matches!(
+            resolve("dep/utils/private/b"),
+            Err(ResolveError::NotExported { .. })
+        ));
+        assert!(matches!(
+            resolve("dep/missing"),
+            Err(ResolveError::NotFound { .. })
+        ));
+        assert!(matches!(
+            resolve("dep/escape"),
+            Err(ResolveError::InvalidPackageTarget { .. })
+        ));
+        assert!(matches!(
+            resolve("mixed"),
+            Err(ResolveError::InvalidPackageJson { .. })
+        ));
+
+        // Self references go through the package's own exports.
+        assert_eq!(resolve("app/feature"), file("/app/lib/feature.js"));
+        assert!(resolve("app/lib/feature.js").is_err());
+    }
+
+    #[test]
+    fn conditions() {
+        let resolver = Resolver::with_fs(fixture()).conditions(&["import", "browser"]);
+        let resolve = |specifier| resolver.resolve(specifier, Path::new("/app"));
+        assert_eq!(resolve("dep"), file("/app/node_modules/dep/esm/index.mjs"));
+        assert_eq!(resolve("sugar"), file("/app/node_modules/sugar/browser.js"));
+        assert_eq!(resolve("#config"), file("/app/config.browser.js"));
+    }
+
+    #[test]
+    fn imports() {
+        let resolver = Resolver::with_fs(fixture());
+        let resolve = |specifier| resolver.resolve(specifier, Path::new("/app/lib"));
+        assert_eq!(resolve("#config"), file("/app/config.js"));
+        assert_eq!(resolve("#internal/a.js"), file("/app/lib/internal/a.js"));
+        assert_eq!(resolve("#dep"), file("/app/node_modules/dep/cjs/index.js"));
+        assert_eq!(resolve("#fs"), Ok(Resolution::Builtin("fs".to_string())));
+        assert_eq!(
+            resolve("#nope"),
+            Err(ResolveError::ImportNotDefined {
+                specifier: "#nope".to_string(),
+                package: PathBuf::from("/app"),
+            })
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// The file system operations the resolver needs, so it can run against something other than the
+/// disk.
+pub trait FileSystem {
+    fn is_file(&self, path: &Path) -> bool;
+    fn is_dir(&self, path: &Path) -> bool;
+    fn read_to_string(&self, path: &Path) -> io::Result<String>;
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        self.read_to_string(path).map(String::into_bytes)
+    }
+}
+
+/// The real file system.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct OsFileSystem;
+
+impl FileSystem for OsFileSystem {
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        std::fs::read_to_string(path)
+    }
+
+    fn read(&self, path: &Path) -> io::Result<Vec<u8>> {
+        std::fs::read(path)
+    }
+}
+
+/// A file system that only exists in memory. Directories are implied by the files in them.
+#[derive(Debug, Default, Clone)]
+pub struct MemoryFileSystem {
+    files: HashMap<PathBuf, String>,
+    dirs: HashSet<PathBuf>,
+}
+
+impl MemoryFileSystem {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn file(mut self, path: impl AsRef<Path>, contents: impl Into<String>) -> Self {
+        let path = normalize(path.as_ref());
+        self.dirs
+            .extend(path.ancestors().skip(1).map(Path::to_path_buf));
+        self.files.insert(path, contents.into());
+        self
+    }
+}
+
+impl FileSystem for MemoryFileSystem {
+    fn is_file(&self, path: &Path) -> bool {
+        self.files.contains_key(&normalize(path))
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        self.dirs.contains(&normalize(path))
+    }
+
+    fn read_to_string(&self, path: &Path) -> io::Result<String> {
+        self.files
+            .get(&normalize(path))
+            .cloned()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, path.display().to_string()))
+    }
+}
+
+/// Resolve `.` and `..` components without touching the file system.
+pub(crate) fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => (),
+                _ => normalized.push(".."),
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
@@ -0,0 +1,492 @@
+use crate::builtins::is_builtin;
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+mod browser;
+mod exports;
+mod fs;
+
+pub(crate) use fs::normalize;
+pub use fs::{FileSystem, MemoryFileSystem, OsFileSystem};
+
+/// What a specifier resolved to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resolution {
+    File(PathBuf),
+    /// A Node core module, like `fs` or `node:test`.
+    Builtin(String),
+    /// A module replaced by `false` in a package.json `browser` field, which bundlers replace by
+    /// an empty object.
+    Empty,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResolveError {
+    NotFound {
+        specifier: String,
+        dir: PathBuf,
+    },
+    InvalidPackageJson {
+        path: PathBuf,
+        message: String,
+    },
+    /// The package has an `exports` field, and it does not export `subpath`.
+    NotExported {
+        specifier: String,
+        package: PathBuf,
+        subpath: String,
+    },
+    /// A `#name` specifier that is not in the `imports` of the package.
+    ImportNotDefined {
+        specifier: String,
+        package: PathBuf,
+    },
+    /// An `exports` or `imports` target that is not a relative path inside the package.
+    InvalidPackageTarget {
+        specifier: String,
+        package: PathBuf,
+        target: String,
+    },
+}
+
+impl fmt::Display for ResolveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ResolveError::NotFound { specifier, dir } => write!(
+                f,
+                "Cannot find module '{}' from '{}'",
+                specifier,
+                dir.display()
+            ),
+            ResolveError::InvalidPackageJson { path, message } => {
+                write!(f, "Invalid package.json {}: {}", path.display(), message)
+            }
+            ResolveError::NotExported {
+                specifier,
+                package,
+                subpath,
+            } => write!(
+                f,
+                "Cannot find module '{}': package subpath '{}' is not defined by \"exports\" in {}",
+                specifier,
+                subpath,
+                package.join("package.json").display()
+            ),
+            ResolveError::ImportNotDefined { specifier, package } => write!(
+                f,
+                "Package import specifier '{}' is not defined in {}",
+                specifier,
+                package.join("package.json").display()
+            ),
+            ResolveError::InvalidPackageTarget {
+                specifier,
+                package,
+                target,
+            } => write!(
+                f,
+                "Cannot find module '{}': invalid target '{}' in {}",
+                specifier,
+                target,
+                package.join("package.json").display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ResolveError {}
+
+/// A parsed package.json.
+#[derive(Debug)]
+struct Package {
+    dir: PathBuf,
+    json: serde_json::Value,
+}
+
+impl Package {
+    fn field(&self, name: &str) -> Option<&str> {
+        self.json.get(name).and_then(|value| value.as_str())
+    }
+}
+
+/// Resolves specifiers to files the way Node's `require()` does.
+pub struct Resolver<F = OsFileSystem> {
+    fs: F,
+    extensions: Vec<String>,
+    conditions: Vec<String>,
+    /// Whether an `import` is being resolved, which matches `import` instead of `require`.
+    import: Cell<bool>,
+    browser: bool,
+    packages: RefCell<HashMap<PathBuf, Option<Rc<Package>>>>,
+}
+
+impl Default for Resolver {
+    fn default() -> Self {
+        Self::with_fs(OsFileSystem)
+    }
+}
+
+impl Resolver {
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<F: FileSystem> Resolver<F> {
+    pub fn with_fs(fs: F) -> Self {
+        Self {
+            fs,
+            extensions: vec!["js".to_string(), "json".to_string(), "node".to_string()],
+            conditions: vec!["require".to_string(), "node".to_string()],
+            import: Cell::new(false),
+            browser: false,
+            packages: Default::default(),
+        }
+    }
+
+    /// The extensions to try when a specifier does not point at a file exactly, in order, without
+    /// the leading dot. Defaults to `js`, `json` and `node`.
+    pub fn extensions<S: AsRef<str>>(mut self, extensions: &[S]) -> Self {
+        self.extensions = extensions
+            .iter()
+            .map(|extension| extension.as_ref().trim_start_matches('.').to_string())
+            .collect();
+        self
+    }
+
+    /// The conditions to match in `exports` and `imports` maps. `default` always matches.
+    /// Defaults to `require` and `node`.
+    pub fn conditions<S: AsRef<str>>(mut self, conditions: &[S]) -> Self {
+        self.conditions = conditions
+            .iter()
+            .map(|condition| condition.as_ref().to_string())
+            .collect();
+        self
+    }
+
+    /// Apply the package.json `browser` field, like browserify does. This does not change the
+    /// conditions; add `browser` to them separately if needed.
+    pub fn browser(mut self, browser: bool) -> Self {
+        self.browser = browser;
+        self
+    }
+
+    /// The file system the resolver reads from.
+    pub fn fs(&self) -> &F {
+        &self.fs
+    }
+
+    /// Resolve `specifier` as if it was required by a file in `dir`.
+    pub fn resolve(&self, specifier: &str, dir: &Path) -> Result<Resolution, ResolveError> {
+        match self.browser {
+            true => self.resolve_browser(specifier, dir),
+            false => self.resolve_node(specifier, dir),
+        }
+    }
+
+    /// Resolve `specifier` as if it was imported by a file in `dir`, with an `import` declaration
+    /// or `import()`. The `import` condition matches instead of `require`, if that is one of the
+    /// conditions.
+    pub fn resolve_import(&self, specifier: &str, dir: &Path) -> Result<Resolution, ResolveError> {
+        let import = self.import.replace(true);
+        let resolved = self.resolve(specifier, dir);
+        self.import.set(import);
+        resolved
+    }
+
+    /// Check whether a condition of an `exports` or `imports` map matches.
+    fn matches(&self, condition: &str) -> bool {
+        let has = |condition: &str| self.conditions.iter().any(|c| c == condition);
+        match (condition, self.import.get()) {
+            ("default", _) => true,
+            ("import", true) => has("import") || has("require"),
+            ("require", true) => false,
+            (condition, _) => has(condition),
+        }
+    }
+
+    fn resolve_node(&self, specifier: &str, dir: &Path) -> Result<Resolution, ResolveError> {
+        let not_found = || ResolveError::NotFound {
+            specifier: specifier.to_string(),
+            dir: dir.to_path_buf(),
+        };
+
+        if is_path(specifier) {
+            return self
+                .load(&normalize(&dir.join(specifier)), specifier.ends_with('/'))?
+                .map(Resolution::File)
+                .ok_or_else(not_found);
+        }
+        if specifier.starts_with('#') {
+            return match self.package_scope(dir)? {
+                Some(package) => self
+                    .check_exists(self.resolve_imports(&package, specifier)?)
+                    .ok_or_else(not_found),
+                None => Err(ResolveError::ImportNotDefined {
+                    specifier: specifier.to_string(),
+                    package: dir.to_path_buf(),
+                }),
+            };
+        }
+        if is_builtin(specifier) {
+            return Ok(Resolution::Builtin(specifier.to_string()));
+        }
+
+        let (name, subpath) = split_package_name(specifier).ok_or_else(not_found)?;
+        let subpath = format!(".{}", subpath);
+
+        // A package can require itself by name, through its own exports.
+        if let Some(package) = self.package_scope(dir)? {
+            if let (Some(exports), Some(true)) = (
+                package.json.get("exports"),
+                package.field("name").map(|own_name| own_name == name),
+            ) {
+                let resolved = self.resolve_exports(&package, exports, &subpath, specifier)?;
+                return self.check_exists(resolved).ok_or_else(not_found);
+            }
+        }
+
+        for dir in dir.ancestors() {
+            if dir.file_name().is_some_and(|name| name == "node_modules") {
+                continue;
+            }
+            let package_dir = dir.join("node_modules").join(name);
+            if !self.fs.is_dir(&package_dir) {
+                continue;
+            }
+            if let Some(package) = self.package(&package_dir)? {
+                if let Some(exports) = package.json.get("exports") {
+                    let resolved = self.resolve_exports(&package, exports, &subpath, specifier)?;
+                    return self.check_exists(resolved).ok_or_else(not_found);
+                }
+            }
+            let path = dir.join("node_modules").join(specifier);
+            if let Some(file) = self.load(&path, specifier.ends_with('/'))? {
+                return Ok(Resolution::File(file));
+            }
+        }
+        Err(not_found())
+    }
+
+    /// Files from `exports` and `imports` are used as is, without trying extensions.
+    fn check_exists(&self, resolution: Resolution) -> Option<Resolution> {
+        match &resolution {
+            Resolution::File(file) if !self.fs.is_file(file) => None,
+            _ => Some(resolution),
+        }
+    }
+
+    /// LOAD_AS_FILE and LOAD_AS_DIRECTORY from the Node documentation.
+    fn load(&self, path: &Path, dir_only: bool) -> Result<Option<PathBuf>, ResolveError> {
+        if !dir_only {
+            if let Some(file) = self.load_file(path) {
+                return Ok(Some(file));
+            }
+        }
+        self.load_dir(path)
+    }
+
+    fn load_file(&self, path: &Path) -> Option<PathBuf> {
+        if self.fs.is_file(path) {
+            return Some(path.to_path_buf());
+        }
+        self.extensions.iter().find_map(|extension| {
+            let mut file = path.as_os_str().to_owned();
+            file.push(".");
+            file.push(extension);
+            let file = PathBuf::from(file);
+            self.fs.is_file(&file).then_some(file)
+        })
+    }
+
+    fn load_index(&self, dir: &Path) -> Option<PathBuf> {
+        self.load_file(&dir.join("index"))
+    }
+
+    fn load_dir(&self, dir: &Path) -> Result<Option<PathBuf>, ResolveError> {
+        if !self.fs.is_dir(dir) {
+            return Ok(None);
+        }
+        let package = self.package(dir)?;
+        let main = package.as_ref().and_then(|package| {
+            // A string `browser` field replaces `main`.
+            let browser = package.field("browser").filter(|_| self.browser);
+            browser.or_else(|| package.field("main"))
+        });
+        if let Some(main) = main {
+            let main = normalize(&dir.join(main));
+            if let Some(file) = self.load_file(&main).or_else(|| self.load_index(&main)) {
+                return Ok(Some(file));
+            }
+        }
+        Ok(self.load_index(dir))
+    }
+
+    /// The nearest package.json in `dir` or above, without leaving a `node_modules` package.
+    fn package_scope(&self, dir: &Path) -> Result<Option<Rc<Package>>, ResolveError> {
+        for dir in dir.ancestors() {
+            if dir.file_name().is_some_and(|name| name == "node_modules") {
+                break;
+            }
+            if let Some(package) = self.package(dir)? {
+                return Ok(Some(package));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The package.json in `dir`, if there is one.
+    fn package(&self, dir: &Path) -> Result<Option<Rc<Package>>, ResolveError> {
+        if let Some(package) = self.packages.borrow().get(dir) {
+            return Ok(package.clone());
+        }
+
+        let path = dir.join("package.json");
+        let package = match self.fs.read_to_string(&path) {
+            Ok(text) => {
+                let json = serde_json::from_str(&text).map_err(|error| {
+                    ResolveError::InvalidPackageJson {
+                        path: path.clone(),
+                        message: error.to_string(),
+                    }
+                })?;
+                Some(Rc::new(Package {
+                    dir: dir.to_path_buf(),
+                    json,
+                }))
+            }
+            Err(_) => None,
+        };
+        self.packages
+            .borrow_mut()
+            .insert(dir.to_path_buf(), package.clone());
+        Ok(package)
+    }
+}
+
+/// Split a bare specifier into the package name and the subpath, including its leading `/`:
+/// `@scope/name/sub` becomes `("@scope/name", "/sub")`.
+pub(crate) fn split_package_name(specifier: &str) -> Option<(&str, &str)> {
+    let name_end = if specifier.starts_with('@') {
+        let slash = specifier.find('/')?;
+        specifier[slash + 1..]
+            .find('/')
+            .map_or(specifier.len(), |end| slash + 1 + end)
+    } else {
+        specifier.find('/').unwrap_or(specifier.len())
+    };
+    let name = &specifier[..name_end];
+    if name.is_empty() || name.ends_with('/') || name.contains('\\') || name.contains('%') {
+        return None;
+    }
+    Some((name, &specifier[name_end..]))
+}
+
+/// Relative and absolute paths, as opposed to package names.
+fn is_path(specifier: &str) -> bool {
+    specifier == "."
+        || specifier == ".."
+        || specifier.starts_with("./")
+        || specifier.starts_with("../")
+        || Path::new(specifier).is_absolute()
+        || specifier.starts_with('/')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture() -> Resolver<MemoryFileSystem> {
+        Resolver::with_fs(
+            MemoryFileSystem::new()
+                .file("/app/index.js", "")
+                .file("/app/lib/util.js", "")
+                .file("/app/lib/data.json", "")
+                .file("/app/lib/dir/index.js", "")
+                .file("/app/src/main.js", "")
+                .file("/app/node_modules/a/package.json", r#"{ "main": "lib/a" }"#)
+                .file("/app/node_modules/a/lib/a.js", "")
+                .file("/app/node_modules/a/sub.js", "")
+                .file("/app/node_modules/b/index.js", "")
+                .file("/app/node_modules/b/node_modules/a/index.js", "")
+                .file(
+                    "/app/node_modules/@scope/c/package.json",
+                    r#"{ "main": "dist" }"#,
+                )
+                .file("/app/node_modules/@scope/c/dist/index.js", "")
+                .file(
+                    "/app/node_modules/d/package.json",
+                    r#"{ "main": "missing.js" }"#,
+                )
+                .file("/app/node_modules/d/index.js", "")
+                .file("/app/node_modules/broken/package.json", "{"),
+        )
+    }
+
+    fn resolve(specifier: &str, dir: &str) -> Result<Resolution, ResolveError> {
+        fixture().resolve(specifier, Path::new(dir))
+    }
+
+    fn file(path: &str) -> Result<Resolution, ResolveError> {
+        Ok(Resolution::File(PathBuf::from(path)))
+    }
+
+    #[test]
+    fn paths() {
+        assert_eq!(resolve("./lib/util", "/app"), file("/app/lib/util.js"));
+        assert_eq!(resolve("./lib/util.js", "/app"), file("/app/lib/util.js"));
+        assert_eq!(resolve("./data", "/app/lib"), file("/app/lib/data.json"));
+        assert_eq!(resolve("./dir", "/app/lib"), file("/app/lib/dir/index.js"));
+        assert_eq!(
+            resolve("../lib/dir/", "/app/src"),
+            file("/app/lib/dir/index.js")
+        );
+        assert_eq!(resolve("..", "/app/src"), file("/app/index.js"));
+        assert_eq!(resolve("/app/src/main", "/"), file("/app/src/main.js"));
+        assert_eq!(
+            resolve("./missing", "/app"),
+            Err(ResolveError::NotFound {
+                specifier: "./missing".to_string(),
+                dir: PathBuf::from("/app"),
+            })
+        );
+    }
+
+    #[test]
+    fn packages() {
+        assert_eq!(
+            resolve("a", "/app/src"),
+            file("/app/node_modules/a/lib/a.js")
+        );
+        assert_eq!(resolve("a/sub", "/app"), file("/app/node_modules/a/sub.js"));
+        assert_eq!(
+            resolve("a", "/app/node_modules/b"),
+            file("/app/node_modules/b/node_modules/a/index.js")
+        );
+        assert_eq!(
+            resolve("@scope/c", "/app"),
+            file("/app/node_modules/@scope/c/dist/index.js")
+        );
+        assert_eq!(resolve("d", "/app"), file("/app/node_modules/d/index.js"));
+        assert!(matches!(
+            resolve("broken", "/app"),
+            Err(ResolveError::InvalidPackageJson { .. })
+        ));
+        assert!(resolve("e", "/app").is_err());
+    }
+
+    #[test]
+    fn builtins() {
+        assert_eq!(
+            resolve("fs", "/app"),
+            Ok(Resolution::Builtin("fs".to_string()))
+        );
+        assert_eq!(
+            resolve("node:test", "/app"),
+            Ok(Resolution::Builtin("node:test".to_string()))
+        );
+        assert!(resolve("test", "/app").is_err());
+    }
+}
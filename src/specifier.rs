@@ -0,0 +1,177 @@
+use crate::is_builtin;
+use crate::resolve::split_package_name;
+#[cfg(feature = "serde")]
+use serde_derive::Serialize;
+
+/// What a specifier refers to, judging by its syntax alone.
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize),
+    serde(tag = "type", rename_all = "camelCase")
+)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SpecifierClass {
+    /// A path relative to the requiring file, like `./a` or `..`.
+    Relative,
+    /// An absolute path, like `/a` or `C:\a`.
+    Absolute,
+    /// A package in `node_modules`.
+    Package {
+        /// The package name, like `lodash` or `@scope/name`.
+        name: String,
+        /// The path inside the package in the form `exports` uses: `.` or `./sub`.
+        subpath: String,
+    },
+    /// A Node core module.
+    Builtin {
+        /// The name without the `node:` prefix.
+        name: String,
+        prefixed: bool,
+    },
+    /// An `http:`, `https:`, `data:` or `file:` URL.
+    Url { scheme: String },
+    /// A `#name` import defined by the `imports` of the enclosing package.
+    Import,
+    /// Anything else, like an empty string or an unknown URL scheme.
+    Other,
+}
+
+impl SpecifierClass {
+    pub fn parse(specifier: &str) -> Self {
+        if specifier.starts_with('#') {
+            return SpecifierClass::Import;
+        }
+        if specifier == "."
+            || specifier == ".."
+            || specifier.starts_with("./")
+            || specifier.starts_with("../")
+        {
+            return SpecifierClass::Relative;
+        }
+        if specifier.starts_with('/') || is_drive_path(specifier) {
+            return SpecifierClass::Absolute;
+        }
+        if let Some(name) = specifier.strip_prefix("node:") {
+            return match is_builtin(specifier) {
+                true => SpecifierClass::Builtin {
+                    name: name.to_string(),
+                    prefixed: true,
+                },
+                false => SpecifierClass::Other,
+            };
+        }
+        if let Some(scheme) = scheme(specifier) {
+            return match scheme.to_ascii_lowercase().as_str() {
+                scheme @ ("http" | "https" | "data" | "file") => SpecifierClass::Url {
+                    scheme: scheme.to_string(),
+                },
+                _ => SpecifierClass::Other,
+            };
+        }
+        if is_builtin(specifier) {
+            return SpecifierClass::Builtin {
+                name: specifier.to_string(),
+                prefixed: false,
+            };
+        }
+        match split_package_name(specifier) {
+            Some((name, subpath)) => SpecifierClass::Package {
+                name: name.to_string(),
+                subpath: format!(".{}", subpath),
+            },
+            None => SpecifierClass::Other,
+        }
+    }
+
+    /// The package name, if the specifier loads a package from `node_modules`.
+    pub fn package_name(&self) -> Option<&str> {
+        match self {
+            SpecifierClass::Package { name, .. } => Some(name),
+            _ => None,
+        }
+    }
+}
+
+/// A Windows path like `C:\a` or `C:/a`.
+fn is_drive_path(specifier: &str) -> bool {
+    let bytes = specifier.as_bytes();
+    bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && (bytes[2] == b'\\' || bytes[2] == b'/')
+}
+
+/// The scheme of a URL, without the colon.
+fn scheme(specifier: &str) -> Option<&str> {
+    let (scheme, _) = specifier.split_once(':')?;
+    let mut chars = scheme.chars();
+    let valid = chars.next()?.is_ascii_alphabetic()
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'));
+    valid.then_some(scheme)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn package(name: &str, subpath: &str) -> SpecifierClass {
+        SpecifierClass::Package {
+            name: name.to_string(),
+            subpath: subpath.to_string(),
+        }
+    }
+
+    fn builtin(name: &str, prefixed: bool) -> SpecifierClass {
+        SpecifierClass::Builtin {
+            name: name.to_string(),
+            prefixed,
+        }
+    }
+
+    #[test]
+    fn parse() {
+        let cases = [
+            ("./a", SpecifierClass::Relative),
+            ("..", SpecifierClass::Relative),
+            ("../a/b.js", SpecifierClass::Relative),
+            ("/a/b", SpecifierClass::Absolute),
+            ("C:\\a", SpecifierClass::Absolute),
+            ("lodash", package("lodash", ".")),
+            ("lodash/map", package("lodash", "./map")),
+            ("@scope/name", package("@scope/name", ".")),
+            (
+                "@scope/name/sub/path.js",
+                package("@scope/name", "./sub/path.js"),
+            ),
+            ("fs", builtin("fs", false)),
+            ("fs/promises", builtin("fs/promises", false)),
+            ("node:fs", builtin("fs", true)),
+            ("node:test", builtin("test", true)),
+            ("test", package("test", ".")),
+            ("node:nope", SpecifierClass::Other),
+            (
+                "https://esm.sh/react",
+                SpecifierClass::Url {
+                    scheme: "https".to_string(),
+                },
+            ),
+            (
+                "data:text/javascript,1",
+                SpecifierClass::Url {
+                    scheme: "data".to_string(),
+                },
+            ),
+            ("npm:react", SpecifierClass::Other),
+            ("#internal/a", SpecifierClass::Import),
+            ("@scope", SpecifierClass::Other),
+            ("", SpecifierClass::Other),
+        ];
+        for (specifier, expected) in cases {
+            assert_eq!(SpecifierClass::parse(specifier), expected, "{}", specifier);
+        }
+        assert_eq!(
+            SpecifierClass::parse("@scope/name/sub").package_name(),
+            Some("@scope/name")
+        );
+    }
+}
@@ -0,0 +1,162 @@
+use crate::glob::Glob;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Collects the source files in a directory tree.
+#[derive(Debug, Clone)]
+pub struct Walk {
+    include: Vec<Glob>,
+    exclude: Vec<Glob>,
+    extensions: Vec<String>,
+}
+
+impl Default for Walk {
+    fn default() -> Self {
+        Self {
+            include: vec![],
+            exclude: vec![Glob::new("node_modules")],
+            extensions: vec!["js".to_string(), "cjs".to_string(), "mjs".to_string()],
+        }
+    }
+}
+
+impl Walk {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Only collect files whose path relative to the root matches one of the included globs.
+    pub fn include(mut self, glob: Glob) -> Self {
+        self.include.push(glob);
+        self
+    }
+
+    /// Skip files and directories whose path relative to the root matches the glob. `node_modules`
+    /// is excluded by default.
+    pub fn exclude(mut self, glob: Glob) -> Self {
+        self.exclude.push(glob);
+        self
+    }
+
+    /// Only collect files with one of these extensions, without the leading dot. An empty list
+    /// collects files with any extension. Defaults to `js`, `cjs` and `mjs`.
+    pub fn extensions<S: AsRef<str>>(mut self, extensions: &[S]) -> Self {
+        self.extensions = extensions
+            .iter()
+            .map(|extension| extension.as_ref().trim_start_matches('.').to_string())
+            .collect();
+        self
+    }
+
+    /// List the files below `root` in a stable order. If `root` is a file, it is returned as is.
+    pub fn files(&self, root: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut files = vec![];
+        if fs::metadata(root)?.is_dir() {
+            self.visit(root, Path::new(""), &mut files)?;
+        } else {
+            files.push(root.to_path_buf());
+        }
+        Ok(files)
+    }
+
+    fn visit(&self, dir: &Path, relative: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+        let mut entries = fs::read_dir(dir)?.collect::<io::Result<Vec<_>>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let path = entry.path();
+            let relative = relative.join(entry.file_name());
+            let relative_str = relative.to_string_lossy();
+            if self.exclude.iter().any(|glob| glob.is_match(&relative_str)) {
+                continue;
+            }
+
+            // Symlinked files are followed, symlinked directories are not, to avoid cycles.
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                self.visit(&path, &relative, files)?;
+            } else if (file_type.is_file() || fs::metadata(&path).is_ok_and(|meta| meta.is_file()))
+                && self.is_included(&relative_str, &path)
+            {
+                files.push(path);
+            }
+        }
+        Ok(())
+    }
+
+    fn is_included(&self, relative: &str, path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("");
+        (self.extensions.is_empty() || self.extensions.iter().any(|ext| ext == extension))
+            && (self.include.is_empty() || self.include.iter().any(|glob| glob.is_match(relative)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn files() {
+        let root =
+            std::env::temp_dir().join(format!("require-detective-walk-{}", std::process::id()));
+        for file in &[
+            "index.js",
+            "lib/a.cjs",
+            "lib/b.mjs",
+            "lib/c.ts",
+            "lib/d.min.js",
+            "test/a.js",
+            "node_modules/x/index.js",
+        ] {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, "").unwrap();
+        }
+
+        let relative = |walk: Walk| -> Vec<String> {
+            walk.files(&root)
+                .unwrap()
+                .iter()
+                .map(|path| {
+                    path.strip_prefix(&root)
+                        .unwrap()
+                        .to_string_lossy()
+                        .replace('\\', "/")
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            relative(Walk::new()),
+            vec![
+                "index.js",
+                "lib/a.cjs",
+                "lib/b.mjs",
+                "lib/d.min.js",
+                "test/a.js"
+            ]
+        );
+        assert_eq!(
+            relative(
+                Walk::new()
+                    .include(Glob::new("lib/**"))
+                    .exclude(Glob::new("*.min.js"))
+            ),
+            vec!["lib/a.cjs", "lib/b.mjs"]
+        );
+        assert_eq!(
+            relative(Walk::new().extensions(&["ts"]).exclude(Glob::new("test"))),
+            vec!["lib/c.ts"]
+        );
+        assert_eq!(
+            Walk::new().files(&root.join("lib/c.ts")).unwrap(),
+            vec![root.join("lib/c.ts")]
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
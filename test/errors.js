@@ -0,0 +1,29 @@
+var test = require('tap').test;
+var detective = require('../');
+
+var src = [
+    "var a = require('a');",
+    "var = 10;",
+    "var b = require('b');"
+].join('\n');
+
+test('syntax errors are thrown as SyntaxError', function (t) {
+    try {
+        detective(src);
+        t.fail('should throw');
+    } catch (err) {
+        t.ok(err instanceof SyntaxError, 'is a SyntaxError');
+        t.equal(err.kind, 'unexpectedToken');
+        t.equal(err.pos, 26);
+        t.deepEqual(err.loc, { line: 2, column: 5 });
+    }
+    t.end();
+});
+
+test('tolerant mode reports diagnostics', function (t) {
+    var found = detective.find(src, { tolerant: true });
+    t.deepEqual(found.strings, [ 'a', 'b' ]);
+    t.equal(found.diagnostics.length, 1);
+    t.ok(found.diagnostics[0] instanceof SyntaxError, 'is a SyntaxError');
+    t.end();
+});
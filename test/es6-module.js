@@ -7,3 +7,10 @@ test('es6-module', function (t) {
     t.plan(1);
     t.deepEqual(detective(src, {parse: {sourceType: 'module'}}), [ 'a', 'b' ]);
 });
+
+test('es6-module goal', function (t) {
+    t.plan(3);
+    t.equal(detective.find(src).goal, 'module');
+    t.equal(detective.find('require("a")').goal, 'script');
+    t.throws(function () { detective(src, { goal: 'script' }) }, SyntaxError);
+});
@@ -1,10 +1,12 @@
 require('./both.js')
 require('./chained.js')
 require('./complicated.js')
+require('./errors.js')
 require('./es2019.js')
 require('./es6-module.js')
 require('./generators.js')
 require('./isrequire.js')
+require('./language.js')
 require('./nested.js')
 require('./noargs.js')
 require('./parseopts.js')
@@ -0,0 +1,24 @@
+var test = require('tap').test;
+var detective = require('../');
+
+test('jsx', function (t) {
+    var src = [
+        "var React = require('react');",
+        "import Button from './button';",
+        "module.exports = () => <Button onClick={() => require('./click')}>Don't</Button>;"
+    ].join('\n');
+    t.throws(function () { detective(src) }, SyntaxError);
+    t.deepEqual(detective(src, { language: 'jsx' }), [ 'react', './button', './click' ]);
+    t.end();
+});
+
+test('typescript', function (t) {
+    var src = [
+        "import fs = require('fs');",
+        "import type { Options } from './options';",
+        "export { parse } from './parse';",
+        "const path: PlatformPath = require('path');"
+    ].join('\n');
+    t.deepEqual(detective(src, { language: 'typescript' }), [ 'fs', './parse', 'path' ]);
+    t.end();
+});